@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use idalloc::{ReusePolicy, Slab};
+
+/// The number of ids allocated at a time.
+const LIVE: u32 = 4096;
+/// The number of ids freed and allocated again per iteration.
+const CHURN: usize = 1024;
+
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("churn");
+
+    let policies = [
+        ReusePolicy::Lifo,
+        ReusePolicy::Fifo,
+        ReusePolicy::LowestFirst,
+        ReusePolicy::Quarantine(64),
+    ];
+
+    for policy in policies {
+        group.bench_function(BenchmarkId::from_parameter(format!("{:?}", policy)), |b| {
+            let mut alloc = Slab::<u32>::builder().reuse(policy).build();
+            let mut live = (0..LIVE).map(|_| alloc.next()).collect::<Vec<_>>();
+            let mut state = 0x2545f491u32;
+
+            b.iter(|| {
+                for _ in 0..CHURN {
+                    // NB: xorshift, to free ids in a scattered order.
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    let n = state as usize % live.len();
+                    alloc.free(live[n]);
+                    live[n] = alloc.next();
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, churn);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+//! Derive macro for the `Id` trait of [idalloc].
+//!
+//! This should be used through the `derive` feature of [idalloc], which
+//! re-exports the macro as `idalloc::Id`.
+//!
+//! [idalloc]: https://docs.rs/idalloc
+
+#![deny(missing_docs)]
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Type};
+
+/// Derive `Id` for a single-field tuple struct by delegating to its field.
+///
+/// The none sentinel of the field is used unless a different one is given
+/// with `#[id(none = <expr>)]`, which fails to compile if it is negative
+/// other than `-1`.
+#[proc_macro_derive(Id, attributes(id))]
+pub fn derive_id(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(output) => output.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Expand the implementation of `Id` for the given input.
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let inner = field(input)?;
+    let none = sentinel(&input.attrs)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match none {
+        Some(none) => custom(inner, &none),
+        None => delegate(inner),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::idalloc::Id for #ident #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+/// Get the type of the single field of a tuple struct.
+fn field(input: &DeriveInput) -> syn::Result<&Type> {
+    if let Data::Struct(data) = &input.data {
+        if let Fields::Unnamed(fields) = &data.fields {
+            if fields.unnamed.len() == 1 {
+                return Ok(&fields.unnamed[0].ty);
+            }
+        }
+    }
+
+    Err(syn::Error::new(
+        input.span(),
+        "`Id` can only be derived for tuple structs with a single field",
+    ))
+}
+
+/// Parse the custom none sentinel out of the `#[id(..)]` attributes, if any.
+fn sentinel(attrs: &[Attribute]) -> syn::Result<Option<Expr>> {
+    let mut none = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("id") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("none") {
+                none = Some(meta.value()?.parse::<Expr>()?);
+                return Ok(());
+            }
+
+            Err(meta.error("unsupported `id` attribute, expected `none`"))
+        })?;
+    }
+
+    Ok(none)
+}
+
+/// Implement every method by delegating to the field.
+fn delegate(inner: &Type) -> TokenStream {
+    quote! {
+        const INITIAL: Self = Self(<#inner as ::idalloc::Id>::INITIAL);
+        const NONE: Self = Self(<#inner as ::idalloc::Id>::NONE);
+
+        #[inline(always)]
+        fn as_usize(self) -> usize {
+            ::idalloc::Id::as_usize(self.0)
+        }
+
+        #[inline(always)]
+        fn from_usize(index: usize) -> ::core::option::Option<Self> {
+            ::core::option::Option::Some(Self(<#inner as ::idalloc::Id>::from_usize(index)?))
+        }
+
+        #[inline(always)]
+        fn as_u128(self) -> u128 {
+            ::idalloc::Id::as_u128(self.0)
+        }
+
+        #[inline(always)]
+        fn from_u128(index: u128) -> ::core::option::Option<Self> {
+            ::core::option::Option::Some(Self(<#inner as ::idalloc::Id>::from_u128(index)?))
+        }
+
+        #[inline(always)]
+        fn increment(self) -> Self {
+            Self(::idalloc::Id::increment(self.0))
+        }
+
+        #[inline(always)]
+        fn checked_increment(self) -> ::core::option::Option<Self> {
+            ::core::option::Option::Some(Self(::idalloc::Id::checked_increment(self.0)?))
+        }
+
+        #[inline(always)]
+        fn take(&mut self) -> Self {
+            Self(::idalloc::Id::take(&mut self.0))
+        }
+
+        #[inline(always)]
+        fn expect(self, m: &str) -> Self {
+            Self(::idalloc::Id::expect(self.0, m))
+        }
+
+        #[inline(always)]
+        fn into_option(self) -> ::core::option::Option<Self> {
+            ::core::option::Option::Some(Self(::idalloc::Id::into_option(self.0)?))
+        }
+
+        #[inline(always)]
+        fn is_none(self) -> bool {
+            ::idalloc::Id::is_none(self.0)
+        }
+    }
+}
+
+/// Implement every method for a custom none sentinel, which is skipped over
+/// when mapping the field to an index.
+fn custom(inner: &Type, none: &Expr) -> TokenStream {
+    quote! {
+        const INITIAL: Self = Self({
+            let none: #inner = #none;
+            if none == 0 { 1 } else { 0 }
+        });
+        const NONE: Self = Self({
+            let none: #inner = #none;
+            // NB: negative values other than -1 aren't ids of signed types.
+            #[allow(unused_comparisons)]
+            let valid = none >= 0 || none.wrapping_add(1) == 0;
+            assert!(valid, "the none sentinel can't be negative, unless it is -1");
+            none
+        });
+
+        #[inline(always)]
+        fn as_usize(self) -> usize {
+            ::idalloc::__private::as_usize(::idalloc::Id::as_u128(self))
+        }
+
+        #[inline(always)]
+        fn from_usize(index: usize) -> ::core::option::Option<Self> {
+            <Self as ::idalloc::Id>::from_u128(index as u128)
+        }
+
+        #[inline(always)]
+        fn as_u128(self) -> u128 {
+            ::idalloc::__private::as_u128(self.0, <Self as ::idalloc::Id>::NONE.0)
+        }
+
+        #[inline(always)]
+        fn from_u128(index: u128) -> ::core::option::Option<Self> {
+            let value = ::idalloc::__private::from_u128(index, <Self as ::idalloc::Id>::NONE.0)?;
+            ::core::option::Option::Some(Self(value))
+        }
+
+        #[inline(always)]
+        fn increment(self) -> Self {
+            match ::idalloc::Id::checked_increment(self) {
+                ::core::option::Option::Some(index) => index,
+                ::core::option::Option::None => panic!("index `{}` is out of bounds", self),
+            }
+        }
+
+        #[inline(always)]
+        fn checked_increment(self) -> ::core::option::Option<Self> {
+            let value = ::idalloc::__private::checked_increment(
+                self.0,
+                <Self as ::idalloc::Id>::NONE.0,
+            )?;
+            ::core::option::Option::Some(Self(value))
+        }
+
+        #[inline(always)]
+        fn take(&mut self) -> Self {
+            ::core::mem::replace(self, <Self as ::idalloc::Id>::NONE)
+        }
+
+        #[inline(always)]
+        fn expect(self, m: &str) -> Self {
+            if ::idalloc::Id::is_none(self) {
+                panic!("{}", m);
+            }
+
+            self
+        }
+
+        #[inline(always)]
+        fn into_option(self) -> ::core::option::Option<Self> {
+            if ::idalloc::Id::is_none(self) {
+                return ::core::option::Option::None;
+            }
+
+            ::core::option::Option::Some(self)
+        }
+
+        #[inline(always)]
+        fn is_none(self) -> bool {
+            ::idalloc::Id::as_u128(self.0)
+                == ::idalloc::Id::as_u128(<Self as ::idalloc::Id>::NONE.0)
+        }
+    }
+}
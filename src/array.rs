@@ -0,0 +1,211 @@
+use crate::{AllocError, Id};
+
+/// A [Slab][crate::Slab] backed by an inline array of `N` slots instead of a
+/// heap allocation, which makes it usable without an allocator.
+///
+/// Ids are allocated and reclaimed in exactly the same manner as in a
+/// [Slab][crate::Slab], except that no more than `N` ids can be in use at a
+/// time.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::{AllocError, ArraySlab};
+/// use std::sync::Mutex;
+///
+/// static ALLOC: Mutex<ArraySlab<u32, 2>> = Mutex::new(ArraySlab::new());
+///
+/// let mut alloc = ALLOC.lock().unwrap();
+/// assert_eq!(Ok(0), alloc.try_next());
+/// assert_eq!(Ok(1), alloc.try_next());
+/// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+///
+/// alloc.free(0);
+/// assert_eq!(Ok(0), alloc.try_next());
+/// ```
+pub struct ArraySlab<I, const N: usize>
+where
+    I: Id,
+{
+    data: [I; N],
+    /// The number of slots which have been handed out at least once.
+    len: usize,
+    next: I,
+    /// The last slot in the free list, which is only meaningful while the free
+    /// list isn't empty.
+    tail: I,
+}
+
+impl<I, const N: usize> ArraySlab<I, N>
+where
+    I: Id,
+{
+    /// Construct a new array slab allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::ArraySlab;
+    ///
+    /// const ALLOC: ArraySlab<u8, 16> = ArraySlab::new();
+    ///
+    /// let mut alloc = ALLOC;
+    /// assert_eq!(0, alloc.next());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            data: [I::NONE; N],
+            len: 0,
+            next: I::INITIAL,
+            tail: I::NONE,
+        }
+    }
+
+    /// Get the number of ids this allocator can hand out at a time.
+    ///
+    /// This is `N`, unless `N` is larger than the number of ids that can be
+    /// represented by `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::ArraySlab;
+    ///
+    /// assert_eq!(16, ArraySlab::<u8, 16>::new().capacity());
+    /// assert_eq!(255, ArraySlab::<u8, 300>::new().capacity());
+    /// ```
+    pub fn capacity(&self) -> usize {
+        N.min(I::none().as_usize())
+    }
+
+    /// Allocate the next id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if all slots are in use. See [ArraySlab::try_next] for a
+    /// fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::ArraySlab::<u32, 4>::new();
+    /// assert_eq!(0u32, alloc.next());
+    /// assert_eq!(1u32, alloc.next());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> I {
+        match self.try_next() {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the next id, returning an error instead of panicking
+    /// if all slots are in use.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, ArraySlab};
+    ///
+    /// let mut alloc = ArraySlab::<u8, 300>::new();
+    ///
+    /// for n in 0..u8::MAX {
+    ///     assert_eq!(Ok(n), alloc.try_next());
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// alloc.free(42);
+    /// assert_eq!(Ok(42), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn try_next(&mut self) -> Result<I, AllocError> {
+        let index = self.next;
+        // NB: once every id that `I` can represent has been handed out, the
+        // tail of the free list links to the none sentinel.
+        let full = self.len == I::none().as_usize();
+
+        self.next = if let Some(entry) = self.data[..self.len].get_mut(index.as_usize()) {
+            match entry.take().into_option() {
+                Some(next) => next,
+                None if full => I::none(),
+                None => {
+                    return Err(AllocError::Corrupted {
+                        index: index.as_usize(),
+                    })
+                }
+            }
+        } else {
+            if self.len == self.capacity() {
+                return Err(AllocError::Exhausted);
+            }
+
+            self.len += 1;
+            I::from_usize(self.len).unwrap_or_else(I::none)
+        };
+
+        Ok(index)
+    }
+
+    /// Free the specified id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::ArraySlab::<u32, 4>::new();
+    /// let id = alloc.next();
+    /// assert!(!alloc.free(id + 1));
+    /// assert!(alloc.free(id));
+    /// assert!(!alloc.free(id));
+    /// ```
+    ///
+    /// Freeing an id twice is detected even once every id that `I` can
+    /// represent has been handed out:
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, ArraySlab};
+    ///
+    /// let mut alloc = ArraySlab::<u8, 300>::new();
+    ///
+    /// while alloc.try_next().is_ok() {}
+    ///
+    /// assert!(alloc.free(42));
+    /// assert!(!alloc.free(42));
+    /// assert_eq!(Ok(42), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn free(&mut self, index: I) -> bool {
+        let has_free = self.has_free();
+        let is_tail = self.tail.as_usize() == index.as_usize();
+
+        if let Some(entry) = self.data[..self.len].get_mut(index.as_usize()) {
+            // NB: once every id has been handed out, the tail of the free list
+            // holds the none sentinel just like an allocated slot.
+            if entry.is_none() && !(has_free && is_tail) {
+                if !has_free {
+                    self.tail = index;
+                }
+
+                *entry = self.next;
+                self.next = index;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Test if there are any freed ids waiting to be reused.
+    fn has_free(&self) -> bool {
+        self.next.as_usize() < self.len
+    }
+}
+
+impl<I, const N: usize> Default for ArraySlab<I, N>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,424 @@
+use crate::{AllocError, Id};
+use alloc::boxed::Box;
+
+#[cfg(not(loom))]
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering};
+
+/// An [Id] which has an atomic counterpart, and can be used in an
+/// [AtomicSlab].
+///
+/// This is only implemented for ids which fit in 32 bits, since the head of
+/// the free list packs an id together with a 32-bit tag into a single atomic
+/// word.
+pub trait AtomicId: Id + Send + Sync {
+    /// The atomic type corresponding to this id.
+    type Atomic: Send + Sync;
+
+    /// Construct a new atomic holding the given value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicId as _;
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// let a = 42u32.into_atomic();
+    /// assert_eq!(42u32, u32::load(&a, Ordering::SeqCst));
+    /// ```
+    fn into_atomic(self) -> Self::Atomic;
+
+    /// Load the value of the given atomic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AtomicId as _, Id as _};
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// let a = u16::none().into_atomic();
+    /// assert!(u16::load(&a, Ordering::SeqCst).is_none());
+    /// ```
+    fn load(atomic: &Self::Atomic, order: Ordering) -> Self;
+
+    /// Store the given value in the atomic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicId as _;
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// let a = 0u8.into_atomic();
+    /// u8::store(&a, 7, Ordering::SeqCst);
+    /// assert_eq!(7, u8::load(&a, Ordering::SeqCst));
+    /// ```
+    fn store(atomic: &Self::Atomic, value: Self, order: Ordering);
+
+    /// Store `new` in the atomic if it currently holds `current`, returning
+    /// the previous value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicId as _;
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// let a = 1u32.into_atomic();
+    /// assert_eq!(Err(1), u32::compare_exchange(&a, 0, 2, Ordering::SeqCst, Ordering::SeqCst));
+    /// assert_eq!(Ok(1), u32::compare_exchange(&a, 1, 2, Ordering::SeqCst, Ordering::SeqCst));
+    /// assert_eq!(2, u32::load(&a, Ordering::SeqCst));
+    /// ```
+    fn compare_exchange(
+        atomic: &Self::Atomic,
+        current: Self,
+        new: Self,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Self, Self>;
+
+    /// Get the id as a 32-bit value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AtomicId as _, Id as _};
+    ///
+    /// assert_eq!(255, u8::none().as_u32());
+    /// ```
+    fn as_u32(self) -> u32;
+
+    /// Construct the id from a 32-bit value previously returned by
+    /// [AtomicId::as_u32].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicId as _;
+    ///
+    /// assert_eq!(42u16, u16::from_u32(42));
+    /// ```
+    fn from_u32(value: u32) -> Self;
+}
+
+macro_rules! impl_primitive_atomic_index {
+    ($ty:ident, $atomic:ident) => {
+        impl AtomicId for $ty {
+            type Atomic = $atomic;
+
+            #[inline(always)]
+            fn into_atomic(self) -> Self::Atomic {
+                $atomic::new(self)
+            }
+
+            #[inline(always)]
+            fn load(atomic: &Self::Atomic, order: Ordering) -> Self {
+                atomic.load(order)
+            }
+
+            #[inline(always)]
+            fn store(atomic: &Self::Atomic, value: Self, order: Ordering) {
+                atomic.store(value, order)
+            }
+
+            #[inline(always)]
+            fn compare_exchange(
+                atomic: &Self::Atomic,
+                current: Self,
+                new: Self,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<Self, Self> {
+                atomic.compare_exchange(current, new, success, failure)
+            }
+
+            #[inline(always)]
+            fn as_u32(self) -> u32 {
+                self as u32
+            }
+
+            #[inline(always)]
+            fn from_u32(value: u32) -> Self {
+                value as $ty
+            }
+        }
+    };
+}
+
+impl_primitive_atomic_index!(u8, AtomicU8);
+impl_primitive_atomic_index!(u16, AtomicU16);
+impl_primitive_atomic_index!(u32, AtomicU32);
+
+/// Pack an index and an ABA tag into a single word.
+#[inline(always)]
+fn pack<I>(index: I, tag: u32) -> u64
+where
+    I: AtomicId,
+{
+    (u64::from(tag) << 32) | u64::from(index.as_u32())
+}
+
+/// Unpack an index and an ABA tag from a single word.
+#[inline(always)]
+fn unpack<I>(head: u64) -> (I, u32)
+where
+    I: AtomicId,
+{
+    (I::from_u32(head as u32), (head >> 32) as u32)
+}
+
+/// A fixed-capacity, lock-free id allocator which can be shared across
+/// threads.
+///
+/// Freed ids are kept on a [Treiber stack] threaded through a preallocated
+/// array of links, where the head of the stack is tagged with a counter that
+/// is bumped on every update to protect against the ABA problem. Since the
+/// head is a 64-bit atomic, this is only available on targets which support
+/// them.
+///
+/// [Treiber stack]: https://en.wikipedia.org/wiki/Treiber_stack
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::AtomicSlab;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let alloc = Arc::new(AtomicSlab::<u32>::with_capacity(1024));
+///
+/// let threads = (0..4)
+///     .map(|_| {
+///         let alloc = alloc.clone();
+///
+///         thread::spawn(move || {
+///             for _ in 0..100 {
+///                 let id = alloc.next();
+///                 assert!(alloc.free(id));
+///             }
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for t in threads {
+///     t.join().unwrap();
+/// }
+///
+/// assert!(alloc.next() < 4);
+/// ```
+pub struct AtomicSlab<I>
+where
+    I: AtomicId,
+{
+    /// Links of the free list, indexed by id.
+    links: Box<[I::Atomic]>,
+    /// Which ids are currently allocated.
+    allocated: Box<[AtomicBool]>,
+    /// The tagged head of the free list.
+    head: AtomicU64,
+    /// The next id which has never been allocated.
+    fresh: I::Atomic,
+}
+
+impl<I> AtomicSlab<I>
+where
+    I: AtomicId,
+{
+    /// Construct a new atomic slab allocator which can hand out up to
+    /// `capacity` ids at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is larger than the number of ids which can be
+    /// represented by `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, AtomicSlab};
+    ///
+    /// let alloc = AtomicSlab::<u32>::with_capacity(2);
+    /// assert_eq!(Ok(0), alloc.try_next());
+    /// assert_eq!(Ok(1), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity > I::none().as_usize() {
+            panic!(
+                "capacity `{}` is out of bounds: 0-{}",
+                capacity,
+                I::none().as_usize()
+            );
+        }
+
+        Self {
+            links: (0..capacity).map(|_| I::none().into_atomic()).collect(),
+            allocated: (0..capacity).map(|_| AtomicBool::new(false)).collect(),
+            head: AtomicU64::new(pack(I::none(), 0)),
+            fresh: I::initial().into_atomic(),
+        }
+    }
+
+    /// Get the maximum number of ids that can be allocated at the same time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicSlab;
+    ///
+    /// let alloc = AtomicSlab::<u8>::with_capacity(16);
+    /// assert_eq!(16, alloc.capacity());
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Allocate the next id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocator is at capacity. See [AtomicSlab::try_next] for
+    /// a fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicSlab;
+    ///
+    /// let alloc = AtomicSlab::<u32>::with_capacity(16);
+    /// assert_eq!(0, alloc.next());
+    /// assert_eq!(1, alloc.next());
+    /// alloc.free(0);
+    /// assert_eq!(0, alloc.next());
+    /// ```
+    pub fn next(&self) -> I {
+        match self.try_next() {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the next id, returning an error instead of panicking
+    /// if the allocator is at capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, AtomicSlab};
+    ///
+    /// let alloc = AtomicSlab::<u8>::with_capacity(1);
+    /// assert_eq!(Ok(0), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// alloc.free(0);
+    /// assert_eq!(Ok(0), alloc.try_next());
+    /// ```
+    pub fn try_next(&self) -> Result<I, AllocError> {
+        let index = match self.pop() {
+            Some(index) => index,
+            None => self.fresh()?,
+        };
+
+        self.allocated[index.as_usize()].store(true, Ordering::Release);
+        Ok(index)
+    }
+
+    /// Free the specified id.
+    ///
+    /// Returns `false` if the id is not currently allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::AtomicSlab;
+    ///
+    /// let alloc = AtomicSlab::<u32>::with_capacity(16);
+    /// let id = alloc.next();
+    /// assert!(!alloc.free(id + 1));
+    /// assert!(alloc.free(id));
+    /// assert!(!alloc.free(id));
+    /// ```
+    pub fn free(&self, index: I) -> bool {
+        let allocated = match self.allocated.get(index.as_usize()) {
+            Some(allocated) => allocated,
+            None => return false,
+        };
+
+        if !allocated.swap(false, Ordering::AcqRel) {
+            return false;
+        }
+
+        self.push(index);
+        true
+    }
+
+    /// Pop an id off the free list.
+    fn pop(&self) -> Option<I> {
+        let mut head = self.head.load(Ordering::Acquire);
+
+        loop {
+            let (index, tag) = unpack::<I>(head);
+
+            if index.is_none() {
+                return None;
+            }
+
+            // NB: if the link is concurrently modified, the tag of the head
+            // will have changed and the exchange below fails.
+            let next = I::load(&self.links[index.as_usize()], Ordering::Relaxed);
+            let new = pack(next, tag.wrapping_add(1));
+
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => return Some(index),
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    /// Push an id onto the free list.
+    fn push(&self, index: I) {
+        let link = &self.links[index.as_usize()];
+        let mut head = self.head.load(Ordering::Relaxed);
+
+        loop {
+            let (next, tag) = unpack::<I>(head);
+            I::store(link, next, Ordering::Relaxed);
+            let new = pack(index, tag.wrapping_add(1));
+
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    /// Allocate an id which has never been allocated before.
+    fn fresh(&self) -> Result<I, AllocError> {
+        let mut current = I::load(&self.fresh, Ordering::Relaxed);
+
+        loop {
+            if current.as_usize() >= self.capacity() {
+                return Err(AllocError::Exhausted);
+            }
+
+            let new = current.checked_increment().ok_or(AllocError::Exhausted)?;
+
+            match I::compare_exchange(
+                &self.fresh,
+                current,
+                new,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(current),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{AllocError, Id};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// The number of bits in a single bitmap word.
+const BITS: usize = 64;
+
+/// A bitmap-based id allocator which always hands out the lowest free id.
+///
+/// Allocated ids are tracked one bit per id, and a summary layer keeps one bit
+/// per word which is set when that word is full. Finding a free id therefore
+/// only needs to scan one summary word for every 4096 ids.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::Bitmap;
+///
+/// let mut alloc = Bitmap::<u32>::new();
+/// assert_eq!(0, alloc.next());
+/// assert_eq!(1, alloc.next());
+/// assert_eq!(2, alloc.next());
+/// alloc.free(2);
+/// alloc.free(0);
+/// assert_eq!(0, alloc.next());
+/// assert_eq!(2, alloc.next());
+/// assert_eq!(3, alloc.next());
+///
+/// for n in 4..10_000 {
+///     assert_eq!(n, alloc.next());
+/// }
+///
+/// alloc.free(9_000);
+/// alloc.free(5_000);
+/// assert_eq!(5_000, alloc.next());
+/// assert_eq!(9_000, alloc.next());
+/// assert_eq!(10_000, alloc.next());
+/// ```
+pub struct Bitmap<I>
+where
+    I: Id,
+{
+    /// One bit per id, set if the id is allocated.
+    words: Vec<u64>,
+    /// One bit per word, set if the word is full.
+    summary: Vec<u64>,
+    _marker: PhantomData<I>,
+}
+
+impl<I> Bitmap<I>
+where
+    I: Id,
+{
+    /// Construct a new bitmap allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Bitmap;
+    ///
+    /// let mut alloc = Bitmap::<u32>::new();
+    /// assert_eq!(0, alloc.next());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            summary: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allocate the lowest free id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See [Bitmap::try_next] for a
+    /// fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Bitmap::<u32>::new();
+    /// assert_eq!(0u32, alloc.next());
+    /// assert_eq!(1u32, alloc.next());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> I {
+        match self.try_next() {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the lowest free id, returning an error instead of
+    /// panicking if that is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Bitmap};
+    ///
+    /// let mut alloc = Bitmap::<u8>::new();
+    ///
+    /// for n in 0..u8::MAX {
+    ///     assert_eq!(Ok(n), alloc.try_next());
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// alloc.free(200);
+    /// alloc.free(100);
+    /// assert_eq!(Ok(100), alloc.try_next());
+    /// assert_eq!(Ok(200), alloc.try_next());
+    /// ```
+    pub fn try_next(&mut self) -> Result<I, AllocError> {
+        let word = match self.summary.iter().position(|s| *s != !0) {
+            Some(s) => s * BITS + (!self.summary[s]).trailing_zeros() as usize,
+            None => self.summary.len() * BITS,
+        };
+
+        // NB: words beyond the end of the bitmap are considered empty.
+        let bits = self.words.get(word).copied().unwrap_or_default();
+        let index = word * BITS + (!bits).trailing_zeros() as usize;
+        let id = I::from_usize(index).ok_or(AllocError::Exhausted)?;
+
+        if word == self.words.len() {
+            self.words.push(0);
+
+            if word / BITS == self.summary.len() {
+                self.summary.push(0);
+            }
+        }
+
+        let bits = &mut self.words[word];
+        *bits |= 1 << (index % BITS);
+
+        if *bits == !0 {
+            self.summary[word / BITS] |= 1 << (word % BITS);
+        }
+
+        Ok(id)
+    }
+
+    /// Free the specified id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Bitmap::<u32>::new();
+    /// let id = alloc.next();
+    /// assert!(!alloc.free(id + 1));
+    /// assert!(alloc.free(id));
+    /// assert!(!alloc.free(id));
+    /// ```
+    pub fn free(&mut self, index: I) -> bool {
+        let index = index.as_usize();
+        let word = index / BITS;
+        let mask = 1 << (index % BITS);
+
+        if let Some(bits) = self.words.get_mut(word) {
+            if *bits & mask != 0 {
+                *bits &= !mask;
+                self.summary[word / BITS] &= !(1 << (word % BITS));
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl<I> Default for Bitmap<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{AllocError, Id, Slab, SlabMap};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+/// A task waiting for an id in a [BoundedSlab].
+struct Waiter<I> {
+    waker: Option<Waker>,
+    /// The id handed over to the waiter once one has been released.
+    id: Option<I>,
+}
+
+/// The shared state of a [BoundedSlab].
+struct State<I>
+where
+    I: Id,
+{
+    slab: Slab<I>,
+    /// The number of ids currently allocated, including ids which have been
+    /// handed over to waiters that haven't been polled yet.
+    live: usize,
+    capacity: usize,
+    waiters: SlabMap<u32, Waiter<I>>,
+    /// Waiters in the order they started waiting.
+    queue: VecDeque<u32>,
+}
+
+impl<I> State<I>
+where
+    I: Id,
+{
+    /// Try to allocate an id without waiting, which never jumps ahead of any
+    /// waiters.
+    fn try_next(&mut self) -> Result<I, AllocError> {
+        if self.live >= self.capacity || !self.queue.is_empty() {
+            return Err(AllocError::Exhausted);
+        }
+
+        let id = self.slab.try_next()?;
+        self.live += 1;
+        Ok(id)
+    }
+
+    /// Release an id, handing it over to the first waiter if there is one.
+    fn release(&mut self, id: I) {
+        if let Some(key) = self.queue.pop_front() {
+            let waiter = &mut self.waiters[key];
+            waiter.id = Some(id);
+
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+
+            return;
+        }
+
+        if self.slab.free(id) {
+            self.live -= 1;
+        }
+    }
+}
+
+/// Lock the shared state.
+///
+/// The state is never left inconsistent by a panic, so a poisoned lock is
+/// ignored.
+fn lock<I>(state: &Mutex<State<I>>) -> MutexGuard<'_, State<I>>
+where
+    I: Id,
+{
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A [Slab] which hands out at most a fixed number of ids at a time, where
+/// [acquire][BoundedSlab::acquire] waits asynchronously for an id to be freed
+/// once the capacity has been reached.
+///
+/// Waiters are served in the order they started waiting. Dropping an
+/// [Acquire] future cancels it, and if an id had already been handed over to
+/// it, the id is passed on to the next waiter instead.
+///
+/// This doesn't depend on any particular async runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::BoundedSlab;
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let alloc = BoundedSlab::<u16>::with_capacity(1);
+/// let guard = alloc.try_acquire().unwrap();
+/// assert!(alloc.try_acquire().is_err());
+///
+/// let mut first = pin!(alloc.acquire());
+/// let mut second = pin!(alloc.acquire());
+/// assert!(first.as_mut().poll(&mut cx).is_pending());
+/// assert!(second.as_mut().poll(&mut cx).is_pending());
+///
+/// drop(guard);
+/// assert!(second.as_mut().poll(&mut cx).is_pending());
+///
+/// let guard = match first.as_mut().poll(&mut cx) {
+///     Poll::Ready(guard) => guard,
+///     Poll::Pending => panic!("first waiter should be served first"),
+/// };
+///
+/// assert_eq!(0, guard.id());
+/// ```
+pub struct BoundedSlab<I>
+where
+    I: Id,
+{
+    state: Arc<Mutex<State<I>>>,
+}
+
+impl<I> BoundedSlab<I>
+where
+    I: Id,
+{
+    /// Construct a new bounded slab allocator, which hands out at most
+    /// `capacity` ids at a time.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                slab: Slab::new(),
+                live: 0,
+                capacity,
+                waiters: SlabMap::new(),
+                queue: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Get the maximum number of ids that can be handed out at a time.
+    pub fn capacity(&self) -> usize {
+        lock(&self.state).capacity
+    }
+
+    /// Try to acquire an id without waiting, returning
+    /// [AllocError::Exhausted] if the allocator is at capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, BoundedSlab};
+    ///
+    /// let alloc = BoundedSlab::<u16>::with_capacity(2);
+    /// let a = alloc.try_acquire().unwrap();
+    /// let b = alloc.try_acquire().unwrap();
+    /// assert_eq!(Some(AllocError::Exhausted), alloc.try_acquire().err());
+    ///
+    /// drop(a);
+    /// assert_eq!(0, alloc.try_acquire().unwrap().id());
+    /// ```
+    pub fn try_acquire(&self) -> Result<BoundedGuard<I>, AllocError> {
+        let id = lock(&self.state).try_next()?;
+
+        Ok(BoundedGuard {
+            state: self.state.clone(),
+            id,
+        })
+    }
+
+    /// Acquire an id, waiting for one to be freed if the allocator is at
+    /// capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::BoundedSlab;
+    /// use std::future::Future;
+    /// use std::pin::pin;
+    /// use std::task::{Context, Poll, Waker};
+    ///
+    /// let mut cx = Context::from_waker(Waker::noop());
+    ///
+    /// let alloc = BoundedSlab::<u16>::with_capacity(1);
+    /// let guard = alloc.try_acquire().unwrap();
+    ///
+    /// // A cancelled waiter gives up its place in line.
+    /// let mut cancelled = Box::pin(alloc.acquire());
+    /// assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+    /// drop(cancelled);
+    ///
+    /// let mut waiting = pin!(alloc.acquire());
+    /// assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    /// drop(guard);
+    /// assert!(matches!(waiting.as_mut().poll(&mut cx), Poll::Ready(..)));
+    /// ```
+    pub fn acquire(&self) -> Acquire<I> {
+        Acquire {
+            state: self.state.clone(),
+            key: None,
+        }
+    }
+}
+
+impl<I> Clone for BoundedSlab<I>
+where
+    I: Id,
+{
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// A future waiting for an id from a [BoundedSlab], created by
+/// [BoundedSlab::acquire].
+pub struct Acquire<I>
+where
+    I: Id,
+{
+    state: Arc<Mutex<State<I>>>,
+    /// The key of the waiter, once the future has started waiting.
+    key: Option<u32>,
+}
+
+impl<I> Future for Acquire<I>
+where
+    I: Id,
+{
+    type Output = BoundedGuard<I>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = lock(&this.state);
+
+        let id = match this.key {
+            None => match state.try_next() {
+                Ok(id) => id,
+                Err(..) => {
+                    let key = state.waiters.insert(Waiter {
+                        waker: Some(cx.waker().clone()),
+                        id: None,
+                    });
+
+                    state.queue.push_back(key);
+                    this.key = Some(key);
+                    return Poll::Pending;
+                }
+            },
+            Some(key) => {
+                let waiter = &mut state.waiters[key];
+
+                match waiter.id.take() {
+                    Some(id) => {
+                        state.waiters.remove(key);
+                        this.key = None;
+                        id
+                    }
+                    None => {
+                        waiter.waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            }
+        };
+
+        drop(state);
+
+        Poll::Ready(BoundedGuard {
+            state: this.state.clone(),
+            id,
+        })
+    }
+}
+
+impl<I> Drop for Acquire<I>
+where
+    I: Id,
+{
+    fn drop(&mut self) {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return,
+        };
+
+        let mut state = lock(&self.state);
+
+        if let Some(waiter) = state.waiters.remove(key) {
+            match waiter.id {
+                Some(id) => state.release(id),
+                None => state.queue.retain(|k| *k != key),
+            }
+        }
+    }
+}
+
+/// An id acquired from a [BoundedSlab], which is freed when the guard is
+/// dropped.
+pub struct BoundedGuard<I>
+where
+    I: Id,
+{
+    state: Arc<Mutex<State<I>>>,
+    id: I,
+}
+
+impl<I> BoundedGuard<I>
+where
+    I: Id,
+{
+    /// Get the id held by this guard.
+    pub fn id(&self) -> I {
+        self.id
+    }
+}
+
+impl<I> fmt::Debug for BoundedGuard<I>
+where
+    I: Id,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("BoundedGuard").field(&self.id).finish()
+    }
+}
+
+impl<I> Drop for BoundedGuard<I>
+where
+    I: Id,
+{
+    fn drop(&mut self) {
+        lock(&self.state).release(self.id);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{AllocError, Id};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+/// A block of `2^order` contiguous ids allocated by a [Buddy] allocator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Block<I> {
+    start: I,
+    order: u32,
+}
+
+impl<I> Block<I>
+where
+    I: Id,
+{
+    /// Get the first id in the block.
+    pub fn start(self) -> I {
+        self.start
+    }
+
+    /// Get the order of the block, where the block holds `2^order` ids.
+    pub fn order(self) -> u32 {
+        self.order
+    }
+
+    /// Get the number of ids in the block.
+    pub fn size(self) -> u128 {
+        1 << self.order
+    }
+
+    /// Get the range of ids covered by the block.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(8);
+    /// let block = alloc.alloc_order(2).unwrap();
+    /// assert_eq!(0..4, block.range());
+    ///
+    /// let mut alloc = Buddy::<i8>::new();
+    /// let block = alloc.alloc_order(alloc.max_order()).unwrap();
+    /// assert_eq!(0..64, block.range());
+    ///
+    /// let mut alloc = Buddy::<i32>::new();
+    /// let block = alloc.alloc_order(alloc.max_order()).unwrap();
+    /// assert_eq!(0..1 << 30, block.range());
+    /// ```
+    pub fn range(self) -> Range<I> {
+        let start = self.start.as_u128();
+        self.start..I::from_u128(start + self.size()).unwrap_or_else(I::none)
+    }
+}
+
+/// Statistics about the free space of a [Buddy] allocator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuddyStats {
+    /// The total number of free ids.
+    pub free_ids: u128,
+    /// The total number of allocated ids.
+    pub allocated_ids: u128,
+    /// The number of free blocks, across all orders.
+    pub free_blocks: usize,
+    /// The order of the largest free block, if any.
+    pub largest_free_order: Option<u32>,
+}
+
+impl BuddyStats {
+    /// The fraction of free ids which are not part of the largest free block,
+    /// from `0.0` when all free ids are contiguous to close to `1.0` when the
+    /// free space is badly fragmented.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(4);
+    /// assert_eq!(0.0, alloc.stats().fragmentation());
+    ///
+    /// let _a = alloc.alloc_order(0).unwrap();
+    /// let b = alloc.alloc_order(3).unwrap();
+    /// alloc.free(b);
+    /// assert!(alloc.stats().fragmentation() > 0.0);
+    /// ```
+    pub fn fragmentation(&self) -> f64 {
+        let largest = match self.largest_free_order {
+            Some(order) => (1u128 << order) as f64,
+            None => return 0.0,
+        };
+
+        1.0 - largest / self.free_ids as f64
+    }
+}
+
+/// A buddy-system allocator handing out blocks of `2^k` contiguous ids.
+///
+/// Blocks are allocated by splitting larger free blocks in halves, and freed
+/// blocks are merged with their buddy, the other half of the block they were
+/// split from, whenever it is also free.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::Buddy;
+///
+/// let mut alloc = Buddy::<u32>::with_max_order(4);
+/// let a = alloc.alloc_order(2).unwrap();
+/// let b = alloc.alloc_order(0).unwrap();
+/// let c = alloc.alloc_order(2).unwrap();
+/// assert_eq!(0..4, a.range());
+/// assert_eq!(4..5, b.range());
+/// assert_eq!(8..12, c.range());
+/// assert_eq!(Some(2), alloc.largest_free_order());
+///
+/// assert!(alloc.free(a));
+/// assert!(alloc.free(b));
+/// assert_eq!(Some(3), alloc.largest_free_order());
+/// assert!(alloc.free(c));
+/// assert_eq!(Some(4), alloc.largest_free_order());
+/// ```
+pub struct Buddy<I>
+where
+    I: Id,
+{
+    /// Free blocks, indexed by order and keyed by their first id.
+    free: Vec<BTreeSet<u128>>,
+    /// Allocated blocks, mapping their first id to their order.
+    allocated: BTreeMap<u128, u32>,
+    /// The total number of free ids.
+    free_ids: u128,
+    _marker: PhantomData<I>,
+}
+
+impl<I> Buddy<I>
+where
+    I: Id,
+{
+    /// Construct a new buddy allocator covering the largest power of two
+    /// number of ids which can be represented by `I`, such that the exclusive
+    /// end of every block is an id too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let alloc = Buddy::<u8>::new();
+    /// assert_eq!(7, alloc.max_order());
+    ///
+    /// // The none sentinel of signed ids is -1, so the end of the last block
+    /// // can't reach it.
+    /// let alloc = Buddy::<i8>::new();
+    /// assert_eq!(6, alloc.max_order());
+    /// ```
+    pub fn new() -> Self {
+        Self::with_max_order(Self::order_limit())
+    }
+
+    /// Construct a new buddy allocator covering `2^max_order` ids.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `2^max_order` ids can't be represented by `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Buddy};
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(3);
+    /// assert!(alloc.alloc_order(3).is_ok());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_order(0));
+    /// ```
+    pub fn with_max_order(max_order: u32) -> Self {
+        if max_order > Self::order_limit() {
+            panic!(
+                "order `{}` is out of bounds: 0-{}",
+                max_order,
+                Self::order_limit()
+            );
+        }
+
+        let mut free = vec![BTreeSet::new(); max_order as usize + 1];
+        free[max_order as usize].insert(I::initial().as_u128());
+
+        Self {
+            free,
+            allocated: BTreeMap::new(),
+            free_ids: 1 << max_order,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the order of the largest block this allocator can hand out.
+    pub fn max_order(&self) -> u32 {
+        self.free.len() as u32 - 1
+    }
+
+    /// Allocate a block of `2^order` contiguous ids, aligned to its size.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Buddy};
+    ///
+    /// let mut alloc = Buddy::<u16>::with_max_order(10);
+    /// let block = alloc.alloc_order(4).unwrap();
+    /// assert_eq!(0, block.start());
+    /// assert_eq!(16, block.size());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_order(11));
+    /// ```
+    pub fn alloc_order(&mut self, order: u32) -> Result<Block<I>, AllocError> {
+        let mut current = (order as usize..self.free.len())
+            .find(|&o| !self.free[o].is_empty())
+            .ok_or(AllocError::Exhausted)?;
+
+        let start = self.free[current]
+            .pop_first()
+            .ok_or(AllocError::Exhausted)?;
+
+        // Split the block, handing the upper halves back to the free lists.
+        while current > order as usize {
+            current -= 1;
+            self.free[current].insert(start + (1 << current));
+        }
+
+        self.allocated.insert(start, order);
+        self.free_ids -= 1 << order;
+
+        Ok(Block {
+            start: I::from_u128(start).unwrap_or_else(I::none),
+            order,
+        })
+    }
+
+    /// Free the specified block, merging it with its buddy as long as the
+    /// buddy is free.
+    ///
+    /// Returns `false` if the block is not allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(4);
+    /// let block = alloc.alloc_order(1).unwrap();
+    /// assert!(alloc.free(block));
+    /// assert!(!alloc.free(block));
+    /// ```
+    pub fn free(&mut self, block: Block<I>) -> bool {
+        let mut start = block.start.as_u128();
+        let mut order = block.order;
+
+        if self.allocated.get(&start) != Some(&order) {
+            return false;
+        }
+
+        self.allocated.remove(&start);
+        self.free_ids += 1 << order;
+
+        while order < self.max_order() {
+            let buddy = start ^ (1 << order);
+
+            if !self.free[order as usize].remove(&buddy) {
+                break;
+            }
+
+            start = u128::min(start, buddy);
+            order += 1;
+        }
+
+        self.free[order as usize].insert(start);
+        true
+    }
+
+    /// Get the order of the largest free block, or `None` if every id is
+    /// allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(1);
+    /// assert_eq!(Some(1), alloc.largest_free_order());
+    /// alloc.alloc_order(0).unwrap();
+    /// assert_eq!(Some(0), alloc.largest_free_order());
+    /// alloc.alloc_order(0).unwrap();
+    /// assert_eq!(None, alloc.largest_free_order());
+    /// ```
+    pub fn largest_free_order(&self) -> Option<u32> {
+        let order = self.free.iter().rposition(|f| !f.is_empty())?;
+        Some(order as u32)
+    }
+
+    /// Get statistics about the free space of the allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Buddy;
+    ///
+    /// let mut alloc = Buddy::<u32>::with_max_order(4);
+    /// alloc.alloc_order(0).unwrap();
+    ///
+    /// let stats = alloc.stats();
+    /// assert_eq!(15, stats.free_ids);
+    /// assert_eq!(1, stats.allocated_ids);
+    /// assert_eq!(4, stats.free_blocks);
+    /// assert_eq!(Some(3), stats.largest_free_order);
+    /// ```
+    pub fn stats(&self) -> BuddyStats {
+        BuddyStats {
+            free_ids: self.free_ids,
+            allocated_ids: (1 << self.max_order()) - self.free_ids,
+            free_blocks: self.free.iter().map(BTreeSet::len).sum(),
+            largest_free_order: self.largest_free_order(),
+        }
+    }
+
+    /// The largest order supported by `I`, which excludes the none sentinel
+    /// and the largest id, so that the end of every block is an id.
+    fn order_limit() -> u32 {
+        127 - (I::none().as_u128() - 1).leading_zeros()
+    }
+}
+
+impl<I> Default for Buddy<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
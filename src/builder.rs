@@ -0,0 +1,206 @@
+use crate::reserved::Reserved;
+use crate::{Id, ReusePolicy, Slab};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::{Bound, Range, RangeBounds};
+
+impl<I> Slab<I>
+where
+    I: Id,
+{
+    /// Construct a builder for a slab allocator which never hands out some
+    /// ids, or which reuses freed ids in a different order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// let mut alloc = Slab::<u16>::builder().start_at(1).build();
+    /// assert_eq!(1, alloc.next());
+    /// ```
+    pub fn builder() -> SlabBuilder<I> {
+        SlabBuilder::new()
+    }
+
+    /// Get the policy deciding which freed id to reuse next.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{ReusePolicy, Slab};
+    ///
+    /// assert_eq!(ReusePolicy::Lifo, Slab::<u32>::new().reuse_policy());
+    ///
+    /// let alloc = Slab::<u32>::builder().reuse(ReusePolicy::Fifo).build();
+    /// assert_eq!(ReusePolicy::Fifo, alloc.reuse_policy());
+    /// ```
+    pub fn reuse_policy(&self) -> ReusePolicy {
+        self.reuse.policy()
+    }
+}
+
+/// A builder for a [Slab] which never hands out reserved ids, or which reuses
+/// freed ids in a different order, created by [Slab::builder].
+///
+/// Reserved ids don't take up any memory, so reserving a large range is cheap.
+/// The reservations, the maximum id and the reuse policy are stored in
+/// snapshots and when the slab is serialized, so a restored slab is configured
+/// the same way. Ids quarantined by [ReusePolicy::Quarantine] are released
+/// when the slab is restored, though.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::{AllocError, Slab};
+///
+/// let mut alloc = Slab::<u16>::builder()
+///     .start_at(1)
+///     .reserve(3..=4)
+///     .max(6)
+///     .build();
+///
+/// assert_eq!(4, alloc.capacity());
+/// assert_eq!(Ok(1), alloc.try_next());
+/// assert_eq!(Ok(2), alloc.try_next());
+/// assert_eq!(Ok(5), alloc.try_next());
+/// assert_eq!(Ok(6), alloc.try_next());
+/// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+///
+/// assert!(!alloc.is_allocated(3));
+/// assert!(!alloc.free(3));
+/// assert!(alloc.free(2));
+/// assert_eq!(Ok(2), alloc.try_next());
+/// ```
+pub struct SlabBuilder<I> {
+    reserved: Vec<Range<usize>>,
+    limit: usize,
+    policy: ReusePolicy,
+    _marker: PhantomData<I>,
+}
+
+impl<I> SlabBuilder<I>
+where
+    I: Id,
+{
+    /// Construct a builder which reserves no ids.
+    pub fn new() -> Self {
+        Self {
+            reserved: Vec::new(),
+            limit: I::none().as_usize(),
+            policy: ReusePolicy::Lifo,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reserve all ids below `start`, so that it is the first id handed out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// let mut alloc = Slab::<u64>::builder().start_at(1 << 62).build();
+    /// assert_eq!(1 << 62, alloc.next());
+    /// assert_eq!((1 << 62) + 1, alloc.next());
+    /// assert_eq!(2, alloc.high_water_mark());
+    /// ```
+    pub fn start_at(self, start: I) -> Self {
+        self.reserve(..start)
+    }
+
+    /// Reserve the given range of ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// let mut alloc = Slab::<u16>::builder()
+    ///     .reserve(0..1)
+    ///     .reserve(0xfff0..=0xfffe)
+    ///     .build();
+    ///
+    /// assert_eq!(0xfff0 - 1, alloc.capacity());
+    /// assert_eq!(1, alloc.next());
+    /// ```
+    pub fn reserve<R>(mut self, range: R) -> Self
+    where
+        R: RangeBounds<I>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => start.as_usize(),
+            Bound::Excluded(start) => start.as_usize().saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => end.as_usize().saturating_add(1),
+            Bound::Excluded(end) => end.as_usize(),
+            Bound::Unbounded => usize::MAX,
+        };
+
+        if start < end {
+            self.reserved.push(start..end);
+        }
+
+        self
+    }
+
+    /// Set the largest id which can be handed out, after which the slab
+    /// reports that it is exhausted.
+    pub fn max(mut self, max: I) -> Self {
+        self.limit = max.as_usize().saturating_add(1).min(I::none().as_usize());
+        self
+    }
+
+    /// Set the order in which freed ids are reused, which defaults to
+    /// [ReusePolicy::Lifo].
+    pub fn reuse(mut self, policy: ReusePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Build the slab allocator.
+    pub fn build(self) -> Slab<I> {
+        let mut ranges = self.reserved;
+        let mut limit = self.limit;
+        ranges.sort_unstable_by_key(|range| range.start);
+
+        let mut reserved = Vec::<Range<usize>>::with_capacity(ranges.len());
+
+        for range in ranges {
+            if range.start >= limit {
+                break;
+            }
+
+            match reserved.last_mut() {
+                Some(last) if range.start <= last.end => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => reserved.push(range),
+            }
+        }
+
+        // NB: ids reserved up to the limit are excluded by lowering the limit
+        // instead, so that they never take up any slots.
+        if let Some(last) = reserved.last() {
+            if last.end >= limit {
+                limit = last.start;
+                reserved.pop();
+            }
+        }
+
+        let reserved = Reserved::new(reserved).expect("merged ranges are sorted and non-adjacent");
+        Slab::with_config(reserved, limit, self.policy)
+    }
+}
+
+impl<I> Default for SlabBuilder<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
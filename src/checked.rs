@@ -0,0 +1,273 @@
+use crate::{AllocError, Id, Slab};
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::vec::Vec;
+
+/// The source of owner tags for [CheckedSlab]s.
+///
+/// NB: this is an `AtomicUsize` so that it's available on targets without
+/// 64-bit atomics.
+static NEXT_OWNER: AtomicUsize = AtomicUsize::new(0);
+
+/// An id handed out by a [CheckedSlab], which combines the allocated index
+/// with a tag identifying the slab that allocated it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CheckedId<I> {
+    index: I,
+    owner: u64,
+}
+
+impl<I> CheckedId<I>
+where
+    I: Id,
+{
+    /// Construct a new checked id out of its raw components.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::CheckedId;
+    ///
+    /// let id = CheckedId::new(4u32, 2);
+    /// assert_eq!(4, id.index());
+    /// assert_eq!(2, id.owner());
+    /// ```
+    pub fn new(index: I, owner: u64) -> Self {
+        Self { index, owner }
+    }
+
+    /// Get the allocated index.
+    pub fn index(self) -> I {
+        self.index
+    }
+
+    /// Get the tag of the slab which allocated this id.
+    pub fn owner(self) -> u64 {
+        self.owner
+    }
+}
+
+impl<I> fmt::Display for CheckedId<I>
+where
+    I: Id,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}@{}", self.index, self.owner)
+    }
+}
+
+/// The state of a single slot in a [CheckedSlab].
+struct Slot {
+    live: bool,
+    /// Where the slot was last freed, if it is free.
+    freed_at: Option<Backtrace>,
+}
+
+/// A [Slab] which tracks the state of every slot explicitly, and panics with a
+/// detailed message when it is misused instead of silently ignoring it.
+///
+/// This catches double frees, frees of ids which were never allocated, and
+/// frees of ids allocated by another slab. It is intended for debugging, since
+/// it uses more memory than a [Slab].
+///
+/// If backtraces are enabled through the `RUST_LIB_BACKTRACE` or
+/// `RUST_BACKTRACE` environment variables, a backtrace is captured every time
+/// an id is freed, and a double free reports where the id was originally
+/// freed.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::CheckedSlab;
+///
+/// let mut alloc = CheckedSlab::<u32>::new();
+///
+/// let a = alloc.next();
+/// let b = alloc.next();
+/// assert_eq!(0, a.index());
+/// assert_eq!(1, b.index());
+///
+/// alloc.free(a);
+/// assert!(!alloc.is_live(a));
+/// assert_eq!(0, alloc.next().index());
+/// ```
+///
+/// Freeing an id twice panics:
+///
+/// ```rust,should_panic
+/// use idalloc::CheckedSlab;
+///
+/// let mut alloc = CheckedSlab::<u32>::new();
+/// let id = alloc.next();
+/// alloc.free(id);
+/// alloc.free(id);
+/// ```
+///
+/// So does freeing an id in another slab than the one which allocated it:
+///
+/// ```rust,should_panic
+/// use idalloc::CheckedSlab;
+///
+/// let mut a = CheckedSlab::<u32>::new();
+/// let mut b = CheckedSlab::<u32>::new();
+/// b.next();
+/// b.free(a.next());
+/// ```
+pub struct CheckedSlab<I>
+where
+    I: Id,
+{
+    slab: Slab<I>,
+    slots: Vec<Slot>,
+    owner: u64,
+}
+
+impl<I> CheckedSlab<I>
+where
+    I: Id,
+{
+    /// Construct a new checked slab allocator with a unique owner tag.
+    ///
+    /// Owner tags come from a process-wide counter, which wraps around after
+    /// `usize::MAX` slabs have been constructed. After that, a tag can be
+    /// shared with an earlier slab.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::CheckedSlab;
+    ///
+    /// let a = CheckedSlab::<u32>::new();
+    /// let b = CheckedSlab::<u32>::new();
+    /// assert_ne!(a.owner(), b.owner());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            slab: Slab::new(),
+            slots: Vec::new(),
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed) as u64,
+        }
+    }
+
+    /// Get the tag identifying this slab, which is stored in every id it
+    /// allocates.
+    pub fn owner(&self) -> u64 {
+        self.owner
+    }
+
+    /// Allocate the next id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See
+    /// [CheckedSlab::try_next] for a fallible alternative.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> CheckedId<I> {
+        match self.try_next() {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the next id, returning an error instead of panicking
+    /// if that is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, CheckedSlab};
+    ///
+    /// let mut alloc = CheckedSlab::<u8>::new();
+    ///
+    /// for _ in 0..u8::MAX {
+    ///     assert!(alloc.try_next().is_ok());
+    /// }
+    ///
+    /// assert_eq!(Some(AllocError::Exhausted), alloc.try_next().err());
+    /// ```
+    pub fn try_next(&mut self) -> Result<CheckedId<I>, AllocError> {
+        let index = self.slab.try_next()?;
+
+        let slot = Slot {
+            live: true,
+            freed_at: None,
+        };
+
+        if index.as_usize() == self.slots.len() {
+            self.slots.push(slot);
+        } else {
+            self.slots[index.as_usize()] = slot;
+        }
+
+        Ok(CheckedId {
+            index,
+            owner: self.owner,
+        })
+    }
+
+    /// Test if the given id is currently allocated by this slab.
+    pub fn is_live(&self, id: CheckedId<I>) -> bool {
+        id.owner == self.owner
+            && self
+                .slots
+                .get(id.index.as_usize())
+                .is_some_and(|slot| slot.live)
+    }
+
+    /// Free the specified id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id was allocated by another slab, was never allocated by
+    /// this slab, or has already been freed.
+    ///
+    /// ```rust,should_panic
+    /// use idalloc::{CheckedId, CheckedSlab};
+    ///
+    /// let mut alloc = CheckedSlab::<u32>::new();
+    /// alloc.free(CheckedId::new(42, alloc.owner()));
+    /// ```
+    pub fn free(&mut self, id: CheckedId<I>) {
+        if id.owner != self.owner {
+            panic!(
+                "id `{}` was allocated by checked slab #{}, but freed in checked slab #{}",
+                id.index, id.owner, self.owner
+            );
+        }
+
+        let slot = match self.slots.get_mut(id.index.as_usize()) {
+            Some(slot) => slot,
+            None => panic!(
+                "id `{}` was never allocated by checked slab #{}",
+                id.index, self.owner
+            ),
+        };
+
+        if !slot.live {
+            match &slot.freed_at {
+                Some(backtrace) if backtrace.status() == BacktraceStatus::Captured => panic!(
+                    "id `{}` freed twice in checked slab #{}, it was first freed at:\n{}",
+                    id.index, self.owner, backtrace
+                ),
+                _ => panic!(
+                    "id `{}` freed twice in checked slab #{}",
+                    id.index, self.owner
+                ),
+            }
+        }
+
+        slot.live = false;
+        slot.freed_at = Some(Backtrace::capture());
+        self.slab.free(id.index);
+    }
+}
+
+impl<I> Default for CheckedSlab<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
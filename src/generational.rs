@@ -0,0 +1,237 @@
+use crate::{AllocError, Id, Slab};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// An id handed out by a [GenerationalSlab], which combines a slot index with
+/// the generation of the slot at the time it was allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GenerationalId<I> {
+    index: I,
+    generation: u32,
+}
+
+impl<I> GenerationalId<I>
+where
+    I: Id,
+{
+    /// Construct a new generational id out of its raw components.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::GenerationalId;
+    ///
+    /// let id = GenerationalId::new(4u32, 2);
+    /// assert_eq!(4, id.index());
+    /// assert_eq!(2, id.generation());
+    /// ```
+    pub fn new(index: I, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// Get the index of the slot this id refers to.
+    pub fn index(self) -> I {
+        self.index
+    }
+
+    /// Get the generation of the slot this id was allocated in.
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl<I> fmt::Display for GenerationalId<I>
+where
+    I: Id,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}v{}", self.index, self.generation)
+    }
+}
+
+/// The state of a single slot in a [GenerationalSlab].
+#[derive(Clone, Copy)]
+struct Slot {
+    generation: u32,
+    live: bool,
+}
+
+/// A slab-based id allocator which tags every id with a generation, so that
+/// stale ids which have been [freed][GenerationalSlab::free] can be told apart
+/// from ids which reuse the same slot.
+///
+/// The generation of a slot is bumped every time it is freed, and wraps
+/// around once it reaches `u32::MAX`.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::GenerationalSlab;
+///
+/// let mut alloc = GenerationalSlab::<u32>::new();
+///
+/// let a = alloc.next();
+/// assert!(alloc.is_live(a));
+/// assert!(alloc.free(a));
+/// assert!(!alloc.is_live(a));
+///
+/// let b = alloc.next();
+/// assert_eq!(a.index(), b.index());
+/// assert_ne!(a, b);
+///
+/// assert!(!alloc.free(a));
+/// assert!(alloc.is_live(b));
+/// ```
+pub struct GenerationalSlab<I>
+where
+    I: Id,
+{
+    slab: Slab<I>,
+    slots: Vec<Slot>,
+}
+
+impl<I> GenerationalSlab<I>
+where
+    I: Id,
+{
+    /// Construct a new generational slab allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::GenerationalSlab;
+    ///
+    /// let mut alloc = GenerationalSlab::<u32>::new();
+    /// let id = alloc.next();
+    /// assert_eq!(0, id.index());
+    /// assert_eq!(0, id.generation());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            slab: Slab::new(),
+            slots: Vec::new(),
+        }
+    }
+
+    /// Allocate the next id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See
+    /// [GenerationalSlab::try_next] for a fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::GenerationalSlab;
+    ///
+    /// let mut alloc = GenerationalSlab::<u32>::new();
+    /// let a = alloc.next();
+    /// alloc.free(a);
+    /// let b = alloc.next();
+    /// assert_eq!(0, b.index());
+    /// assert_eq!(1, b.generation());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> GenerationalId<I> {
+        match self.try_next() {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the next id, returning an error instead of panicking
+    /// if that is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, GenerationalSlab};
+    ///
+    /// let mut alloc = GenerationalSlab::<u8>::new();
+    ///
+    /// for _ in 0..u8::MAX {
+    ///     assert!(alloc.try_next().is_ok());
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn try_next(&mut self) -> Result<GenerationalId<I>, AllocError> {
+        let index = self.slab.try_next()?;
+
+        if index.as_usize() == self.slots.len() {
+            self.slots.push(Slot {
+                generation: 0,
+                live: false,
+            });
+        }
+
+        let slot = &mut self.slots[index.as_usize()];
+        slot.live = true;
+
+        Ok(GenerationalId {
+            index,
+            generation: slot.generation,
+        })
+    }
+
+    /// Test if the given id is currently allocated, and has not been freed
+    /// since.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{GenerationalId, GenerationalSlab};
+    ///
+    /// let mut alloc = GenerationalSlab::<u32>::new();
+    /// let id = alloc.next();
+    /// assert!(alloc.is_live(id));
+    /// assert!(!alloc.is_live(GenerationalId::new(0, 1)));
+    /// assert!(!alloc.is_live(GenerationalId::new(1, 0)));
+    ///
+    /// alloc.free(id);
+    /// assert!(!alloc.is_live(id));
+    /// assert!(!alloc.is_live(GenerationalId::new(0, 1)));
+    /// ```
+    pub fn is_live(&self, id: GenerationalId<I>) -> bool {
+        match self.slots.get(id.index.as_usize()) {
+            Some(slot) => slot.live && slot.generation == id.generation,
+            None => false,
+        }
+    }
+
+    /// Free the specified id, bumping the generation of its slot.
+    ///
+    /// Returns `false` if the id is not live, either because it was never
+    /// allocated or because it is stale.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::GenerationalSlab;
+    ///
+    /// let mut alloc = GenerationalSlab::<u32>::new();
+    /// let id = alloc.next();
+    /// assert!(alloc.free(id));
+    /// assert!(!alloc.free(id));
+    /// ```
+    pub fn free(&mut self, id: GenerationalId<I>) -> bool {
+        if !self.is_live(id) {
+            return false;
+        }
+
+        let slot = &mut self.slots[id.index.as_usize()];
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.live = false;
+        self.slab.free(id.index)
+    }
+}
+
+impl<I> Default for GenerationalSlab<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{AllocError, Id};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+/// The number of bits of an id consumed by each level of the tree.
+const SHIFT: u32 = 6;
+/// The number of bits in a leaf, and the number of children of a branch.
+const BITS: u32 = 1 << SHIFT;
+/// Mask to extract the slot of an id at a given level.
+const MASK: u128 = (BITS - 1) as u128;
+
+/// Get the index of a bound of a range of ids.
+///
+/// Negative values map to the index of the none sentinel, but as bounds they
+/// lie below every id.
+fn bound<I>(value: I) -> u128
+where
+    I: Id,
+{
+    let index = value.as_u128();
+
+    if index == I::none().as_u128() && !value.is_none() {
+        return I::initial().as_u128();
+    }
+
+    index
+}
+
+/// A node in the radix tree.
+enum Node {
+    /// A leaf, with one bit per id, set if the id is allocated.
+    Leaf(u64),
+    /// A branch pointing to up to 64 children.
+    Branch(Branch),
+}
+
+/// A branch in the radix tree.
+///
+/// Children are stored compactly, so that only children which are present
+/// take up any space. The position of a child in `children` is the number of
+/// present children which precede it.
+#[derive(Default)]
+struct Branch {
+    /// One bit per child, set if the child is present.
+    present: u64,
+    /// One bit per child, set if the child is full.
+    full: u64,
+    children: Vec<Node>,
+}
+
+impl Branch {
+    /// Get the position of the given child in `children`.
+    #[inline(always)]
+    fn position(&self, slot: u32) -> usize {
+        (self.present & ((1 << slot) - 1)).count_ones() as usize
+    }
+
+    /// Get the given child, if it is present.
+    fn get(&self, slot: u32) -> Option<&Node> {
+        if self.present & (1 << slot) == 0 {
+            return None;
+        }
+
+        Some(&self.children[self.position(slot)])
+    }
+}
+
+impl Node {
+    /// Construct a new, empty node for the given level.
+    fn new(level: u32) -> Self {
+        if level == 0 {
+            Node::Leaf(0)
+        } else {
+            Node::Branch(Branch::default())
+        }
+    }
+
+    /// Test if every id covered by this node is allocated.
+    fn is_full(&self) -> bool {
+        match self {
+            Node::Leaf(bits) => *bits == !0,
+            Node::Branch(b) => b.full == !0,
+        }
+    }
+
+    /// Test if no id covered by this node is allocated.
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Leaf(bits) => *bits == 0,
+            Node::Branch(b) => b.present == 0,
+        }
+    }
+
+    /// Test if the given id is allocated.
+    fn contains(&self, level: u32, id: u128) -> bool {
+        let slot = slot(level, id);
+
+        match self {
+            Node::Leaf(bits) => *bits & (1 << slot) != 0,
+            Node::Branch(b) => match b.get(slot) {
+                Some(child) => child.contains(level - 1, id),
+                None => false,
+            },
+        }
+    }
+
+    /// Mark the given id as allocated, creating nodes along the way.
+    fn insert(&mut self, level: u32, id: u128) {
+        let slot = slot(level, id);
+
+        match self {
+            Node::Leaf(bits) => {
+                *bits |= 1 << slot;
+            }
+            Node::Branch(b) => {
+                let position = b.position(slot);
+
+                if b.present & (1 << slot) == 0 {
+                    b.present |= 1 << slot;
+                    b.children.insert(position, Node::new(level - 1));
+                }
+
+                let child = &mut b.children[position];
+                child.insert(level - 1, id);
+
+                if child.is_full() {
+                    b.full |= 1 << slot;
+                }
+            }
+        }
+    }
+
+    /// Mark the given id as free, removing nodes which become empty.
+    ///
+    /// Returns `false` if the id was not allocated.
+    fn remove(&mut self, level: u32, id: u128) -> bool {
+        let slot = slot(level, id);
+
+        match self {
+            Node::Leaf(bits) => {
+                if *bits & (1 << slot) == 0 {
+                    return false;
+                }
+
+                *bits &= !(1 << slot);
+                true
+            }
+            Node::Branch(b) => {
+                if b.present & (1 << slot) == 0 {
+                    return false;
+                }
+
+                let position = b.position(slot);
+                let child = &mut b.children[position];
+
+                if !child.remove(level - 1, id) {
+                    return false;
+                }
+
+                b.full &= !(1 << slot);
+
+                if child.is_empty() {
+                    b.present &= !(1 << slot);
+                    b.children.remove(position);
+                }
+
+                true
+            }
+        }
+    }
+}
+
+/// Get the slot of the given id at the given level.
+#[inline(always)]
+fn slot(level: u32, id: u128) -> u32 {
+    ((id >> (level * SHIFT)) & MASK) as u32
+}
+
+/// Find the lowest free id in `lo..hi` which is covered by the given node,
+/// where `base` is the first id covered by the node.
+fn find(node: Option<&Node>, level: u32, base: u128, lo: u128, hi: u128) -> Option<u128> {
+    let node = match node {
+        Some(node) => node,
+        // NB: missing nodes have no allocated ids.
+        None => return Some(lo),
+    };
+
+    let start = slot(level, lo - base);
+
+    match node {
+        Node::Leaf(bits) => {
+            let free = !bits & (!0 << start);
+
+            if free == 0 {
+                return None;
+            }
+
+            let id = base + u128::from(free.trailing_zeros());
+
+            if id < hi {
+                Some(id)
+            } else {
+                None
+            }
+        }
+        Node::Branch(b) => {
+            let span = 1u128 << (level * SHIFT);
+            let mut candidates = !b.full & (!0 << start);
+
+            while candidates != 0 {
+                let slot = candidates.trailing_zeros();
+                candidates &= candidates - 1;
+
+                let child_base = match span
+                    .checked_mul(u128::from(slot))
+                    .and_then(|offset| base.checked_add(offset))
+                {
+                    Some(child_base) if child_base < hi => child_base,
+                    _ => return None,
+                };
+
+                let lo = u128::max(lo, child_base);
+
+                if let Some(id) = find(b.get(slot), level - 1, child_base, lo, hi) {
+                    return Some(id);
+                }
+            }
+
+            None
+        }
+    }
+}
+
+/// A hierarchical id allocator in the style of the [Linux IDA], suitable for
+/// sparse ids in large id spaces.
+///
+/// Allocated ids are stored in a radix tree of bitmaps, where every branch
+/// keeps track of which of its children are full so that searching for a free
+/// id can skip over them. Memory use is proportional to the number of ids in
+/// use, and not to the largest id handed out.
+///
+/// [Linux IDA]: https://www.kernel.org/doc/html/latest/core-api/idr.html
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::Ida;
+///
+/// let mut alloc = Ida::<u64>::new();
+/// assert_eq!(0, alloc.next());
+/// assert_eq!(Ok(1 << 40), alloc.alloc_at_least(1 << 40));
+/// assert_eq!(Ok((1 << 40) + 1), alloc.alloc_at_least(1 << 40));
+/// assert_eq!(1, alloc.next());
+/// assert!(alloc.free(1 << 40));
+/// assert_eq!(Ok(1000), alloc.alloc_at_least(1000));
+/// assert_eq!(Ok(1 << 40), alloc.alloc_at_least(1 << 40));
+/// assert_eq!(Ok((1 << 40) + 2), alloc.alloc_at_least(1 << 40));
+/// ```
+pub struct Ida<I>
+where
+    I: Id,
+{
+    root: Node,
+    _marker: PhantomData<I>,
+}
+
+impl<I> Ida<I>
+where
+    I: Id,
+{
+    /// Construct a new radix tree allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Ida;
+    ///
+    /// let mut alloc = Ida::<u128>::new();
+    /// assert_eq!(0, alloc.next());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(Self::root_level()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allocate the lowest free id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See [Ida::try_next] for a
+    /// fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Ida::<u64>::new();
+    /// assert_eq!(0u64, alloc.next());
+    /// assert_eq!(1u64, alloc.next());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> I {
+        match self.try_next() {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the lowest free id, returning an error instead of
+    /// panicking if that is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Ida};
+    ///
+    /// let mut alloc = Ida::<u8>::new();
+    ///
+    /// for n in 0..u8::MAX {
+    ///     assert_eq!(Ok(n), alloc.try_next());
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn try_next(&mut self) -> Result<I, AllocError> {
+        self.alloc_at_least(I::initial())
+    }
+
+    /// Allocate the lowest free id which is greater than or equal to `min`.
+    ///
+    /// A negative `min` other than the none sentinel lies below every id, so
+    /// the lowest free id is allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Ida};
+    ///
+    /// let mut alloc = Ida::<u16>::new();
+    /// assert_eq!(Ok(1000), alloc.alloc_at_least(1000));
+    /// assert_eq!(Ok(1001), alloc.alloc_at_least(1000));
+    /// assert_eq!(Ok(0), alloc.alloc_at_least(0));
+    /// assert_eq!(Ok(u16::MAX - 1), alloc.alloc_at_least(u16::MAX - 1));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_at_least(u16::MAX - 1));
+    ///
+    /// let mut alloc = Ida::<i32>::new();
+    /// assert_eq!(Ok(0), alloc.alloc_at_least(-5));
+    /// assert_eq!(Ok(1), alloc.alloc_at_least(i32::MIN));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_at_least(-1));
+    /// ```
+    pub fn alloc_at_least(&mut self, min: I) -> Result<I, AllocError> {
+        self.alloc(bound(min), I::none().as_u128())
+    }
+
+    /// Allocate the lowest free id in the given range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Ida};
+    ///
+    /// let mut alloc = Ida::<u32>::new();
+    /// assert_eq!(Ok(10), alloc.alloc_in_range(10..12));
+    /// assert_eq!(Ok(11), alloc.alloc_in_range(10..12));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_in_range(10..12));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_in_range(12..12));
+    /// alloc.free(10);
+    /// assert_eq!(Ok(0), alloc.alloc_in_range(0..100));
+    /// assert_eq!(Ok(1), alloc.alloc_in_range(0..100));
+    ///
+    /// // Negative bounds lie below every id.
+    /// let mut alloc = Ida::<i32>::new();
+    /// assert_eq!(Ok(0), alloc.alloc_in_range(-5..2));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_in_range(-5..-2));
+    /// ```
+    pub fn alloc_in_range(&mut self, range: Range<I>) -> Result<I, AllocError> {
+        let hi = u128::min(bound(range.end), I::none().as_u128());
+        self.alloc(bound(range.start), hi)
+    }
+
+    /// Test if the given id is allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Ida::<u64>::new();
+    /// let id = alloc.alloc_at_least(1 << 50).unwrap();
+    /// assert!(alloc.contains(id));
+    /// assert!(!alloc.contains(id + 1));
+    /// ```
+    pub fn contains(&self, index: I) -> bool {
+        self.root.contains(Self::root_level(), index.as_u128())
+    }
+
+    /// Free the specified id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Ida::<u64>::new();
+    /// let id = alloc.next();
+    /// assert!(!alloc.free(id + 1));
+    /// assert!(alloc.free(id));
+    /// assert!(!alloc.free(id));
+    /// ```
+    pub fn free(&mut self, index: I) -> bool {
+        self.root.remove(Self::root_level(), index.as_u128())
+    }
+
+    /// Allocate the lowest free id in `lo..hi`.
+    fn alloc(&mut self, lo: u128, hi: u128) -> Result<I, AllocError> {
+        if lo >= hi {
+            return Err(AllocError::Exhausted);
+        }
+
+        let level = Self::root_level();
+        let id = find(Some(&self.root), level, 0, lo, hi).ok_or(AllocError::Exhausted)?;
+        let index = I::from_u128(id).ok_or(AllocError::Exhausted)?;
+        self.root.insert(level, id);
+        Ok(index)
+    }
+
+    /// The level of the root node, which is determined by the width of `I`.
+    fn root_level() -> u32 {
+        let bits = 128 - I::none().as_u128().leading_zeros();
+        bits.div_ceil(SHIFT) - 1
+    }
+}
+
+impl<I> Default for Ida<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,220 @@
+use crate::reserved::Reserved;
+use crate::{Id, Slab};
+use alloc::vec;
+use core::iter::{Enumerate, FusedIterator};
+use core::mem;
+use core::slice;
+
+impl<I> Slab<I>
+where
+    I: Id,
+{
+    /// Get the slot of the tail of the free list if it holds the none
+    /// sentinel, which makes it indistinguishable from an allocated slot.
+    fn none_tail(&self) -> Option<usize> {
+        let slot = self.slot(self.tail);
+
+        if self.has_free() && self.data[slot].is_none() {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the allocated ids, in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let a = alloc.next();
+    /// alloc.next();
+    /// alloc.next();
+    /// alloc.free(a);
+    ///
+    /// assert_eq!(vec![1, 2], alloc.iter_live().collect::<Vec<_>>());
+    /// ```
+    pub fn iter_live(&self) -> IterLive<'_, I> {
+        IterLive {
+            data: self.data.iter().enumerate(),
+            tail: self.none_tail(),
+            reserved: &self.reserved,
+            remaining: self.live,
+        }
+    }
+
+    /// Iterate over the freed ids, in the order they will be reused.
+    ///
+    /// This is the order in which they are linked together, which doesn't
+    /// apply to [ReusePolicy::LowestFirst][crate::ReusePolicy::LowestFirst].
+    /// With [ReusePolicy::Quarantine][crate::ReusePolicy::Quarantine], new ids
+    /// are handed out instead while the first one is quarantined.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let a = alloc.next();
+    /// let b = alloc.next();
+    /// alloc.next();
+    /// alloc.free(a);
+    /// alloc.free(b);
+    ///
+    /// assert_eq!(vec![1, 0], alloc.iter_free().collect::<Vec<_>>());
+    /// ```
+    pub fn iter_free(&self) -> IterFree<'_, I> {
+        IterFree {
+            slab: self,
+            current: self.next,
+            remaining: self.free_len(),
+        }
+    }
+
+    /// Reset the allocator, returning an iterator over the ids which were
+    /// allocated, in increasing order.
+    ///
+    /// The allocator is reset immediately, even if the iterator isn't
+    /// consumed. Reserved ids stay reserved, and the reuse policy is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let a = alloc.next();
+    /// alloc.next();
+    /// alloc.free(a);
+    ///
+    /// assert_eq!(vec![1], alloc.drain().collect::<Vec<_>>());
+    /// assert!(alloc.is_empty());
+    /// assert_eq!(0, alloc.next());
+    /// ```
+    pub fn drain(&mut self) -> Drain<I> {
+        let tail = self.none_tail();
+        let remaining = self.live;
+        let empty = Self::with_config(self.reserved.clone(), self.limit, self.reuse_policy());
+        let slab = mem::replace(self, empty);
+
+        Drain {
+            data: slab.data.into_iter().enumerate(),
+            tail,
+            reserved: slab.reserved,
+            remaining,
+        }
+    }
+}
+
+/// An iterator over the allocated ids in a [Slab], created by
+/// [Slab::iter_live].
+pub struct IterLive<'a, I> {
+    data: Enumerate<slice::Iter<'a, I>>,
+    /// The free slot which holds the none sentinel, if any.
+    tail: Option<usize>,
+    reserved: &'a Reserved,
+    remaining: usize,
+}
+
+impl<I> Iterator for IterLive<'_, I>
+where
+    I: Id,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        for (slot, entry) in &mut self.data {
+            if entry.is_none() && self.tail != Some(slot) {
+                self.remaining -= 1;
+                return I::from_usize(self.reserved.id_at(slot));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<I> ExactSizeIterator for IterLive<'_, I> where I: Id {}
+
+impl<I> FusedIterator for IterLive<'_, I> where I: Id {}
+
+/// An iterator over the freed ids in a [Slab], created by [Slab::iter_free].
+pub struct IterFree<'a, I>
+where
+    I: Id,
+{
+    slab: &'a Slab<I>,
+    current: I,
+    remaining: usize,
+}
+
+impl<I> Iterator for IterFree<'_, I>
+where
+    I: Id,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.current;
+        self.current = *self.slab.data.get(self.slab.slot(index))?;
+        self.remaining -= 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<I> ExactSizeIterator for IterFree<'_, I> where I: Id {}
+
+impl<I> FusedIterator for IterFree<'_, I> where I: Id {}
+
+/// An iterator over the ids which were allocated in a [Slab], created by
+/// [Slab::drain].
+pub struct Drain<I> {
+    data: Enumerate<vec::IntoIter<I>>,
+    /// The free slot which holds the none sentinel, if any.
+    tail: Option<usize>,
+    reserved: Reserved,
+    remaining: usize,
+}
+
+impl<I> Iterator for Drain<I>
+where
+    I: Id,
+{
+    type Item = I;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        for (slot, entry) in &mut self.data {
+            if entry.is_none() && self.tail != Some(slot) {
+                self.remaining -= 1;
+                return I::from_usize(self.reserved.id_at(slot));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<I> ExactSizeIterator for Drain<I> where I: Id {}
+
+impl<I> FusedIterator for Drain<I> where I: Id {}
@@ -0,0 +1,516 @@
+use crate::snapshot::{crc32, invalid, width};
+use crate::{Id, Slab};
+use std::borrow::ToOwned;
+use std::format;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::vec::Vec;
+
+/// Magic bytes at the start of every journal.
+const MAGIC: [u8; 4] = *b"IDAJ";
+/// The current version of the journal format.
+const VERSION: u8 = 1;
+/// The size of the journal header.
+const HEADER: usize = MAGIC.len() + 2 + 4;
+/// Record of an id being allocated.
+const OP_NEXT: u8 = 1;
+/// Record of an id being freed.
+const OP_FREE: u8 = 2;
+
+/// The name of the snapshot file in a journal directory.
+const SNAPSHOT: &str = "snapshot";
+/// The name of the journal file in a journal directory.
+const JOURNAL: &str = "journal";
+
+/// When a [JournaledSlab] flushes its journal to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Sync after every record. This is the slowest, but no acknowledged
+    /// allocation is ever lost.
+    Always,
+    /// Sync after every `n` records.
+    Every(u32),
+    /// Never sync explicitly, and leave it up to the operating system.
+    Never,
+}
+
+/// Options used when opening a [JournaledSlab].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalOptions {
+    /// When to sync the journal. Defaults to [SyncPolicy::Always].
+    pub sync: SyncPolicy,
+    /// Compact the journal into a new snapshot after this many records, or
+    /// never if `None`. Defaults to `Some(4096)`.
+    pub compact_after: Option<u64>,
+}
+
+impl Default for JournalOptions {
+    fn default() -> Self {
+        Self {
+            sync: SyncPolicy::Always,
+            compact_after: Some(4096),
+        }
+    }
+}
+
+/// A [Slab] which records every allocation in a write-ahead journal, so that
+/// its state survives crashes and restarts.
+///
+/// State is stored in a directory holding a `snapshot`, written using
+/// [Slab::write_snapshot], and a `journal` of every operation performed since
+/// the snapshot was taken. On [open][JournaledSlab::open] the journal is
+/// replayed over the snapshot. A truncated final record, as left behind by a
+/// crash in the middle of a write, is discarded.
+///
+/// Every so often, the journal is [compacted][JournaledSlab::compact] into a
+/// new snapshot.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::JournaledSlab;
+///
+/// let dir = std::env::temp_dir().join(format!("idalloc-journal-{}", std::process::id()));
+/// # let _ = std::fs::remove_dir_all(&dir);
+///
+/// let mut alloc = JournaledSlab::<u32>::open(&dir)?;
+/// assert_eq!(0, alloc.next()?);
+/// assert_eq!(1, alloc.next()?);
+/// assert_eq!(2, alloc.next()?);
+/// assert!(alloc.free(1)?);
+/// drop(alloc);
+///
+/// let mut alloc = JournaledSlab::<u32>::open(&dir)?;
+/// assert_eq!(1, alloc.next()?);
+/// assert_eq!(3, alloc.next()?);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct JournaledSlab<I>
+where
+    I: Id,
+{
+    slab: Slab<I>,
+    dir: PathBuf,
+    journal: File,
+    options: JournalOptions,
+    /// Records written since the journal was last synced.
+    unsynced: u32,
+    /// Records written since the last snapshot.
+    records: u64,
+    /// The length of the journal up to the end of the last record which was
+    /// written successfully.
+    len: u64,
+    /// Set if the journal on disk might be out of step with the allocator,
+    /// which is resolved by compacting it.
+    poisoned: bool,
+}
+
+impl<I> JournaledSlab<I>
+where
+    I: Id,
+{
+    /// Open a journaled slab allocator in the given directory using the
+    /// default [JournalOptions], creating it if it doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::JournaledSlab;
+    /// use std::fs::OpenOptions;
+    /// use std::io::Write as _;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("idalloc-open-{}", std::process::id()));
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    ///
+    /// let mut alloc = JournaledSlab::<u32>::open(&dir)?;
+    /// assert_eq!(0, alloc.next()?);
+    /// drop(alloc);
+    ///
+    /// // Simulate a crash in the middle of writing a record.
+    /// let mut journal = OpenOptions::new().append(true).open(dir.join("journal"))?;
+    /// journal.write_all(&[1, 1])?;
+    /// drop(journal);
+    ///
+    /// let mut alloc = JournaledSlab::<u32>::open(&dir)?;
+    /// assert_eq!(1, alloc.next()?);
+    /// drop(alloc);
+    ///
+    /// let mut alloc = JournaledSlab::<u32>::open(&dir)?;
+    /// assert_eq!(2, alloc.next()?);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn open<P>(dir: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_with(dir, JournalOptions::default())
+    }
+
+    /// Open a journaled slab allocator in the given directory using the given
+    /// options, creating it if it doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{JournalOptions, JournaledSlab, SyncPolicy};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("idalloc-open-with-{}", std::process::id()));
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    ///
+    /// let options = JournalOptions {
+    ///     sync: SyncPolicy::Every(16),
+    ///     compact_after: Some(2),
+    /// };
+    ///
+    /// let mut alloc = JournaledSlab::<u16>::open_with(&dir, options)?;
+    ///
+    /// for n in 0..10 {
+    ///     assert_eq!(n, alloc.next()?);
+    /// }
+    ///
+    /// alloc.sync()?;
+    /// drop(alloc);
+    ///
+    /// let mut alloc = JournaledSlab::<u16>::open_with(&dir, options)?;
+    /// assert_eq!(10, alloc.next()?);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn open_with<P>(dir: P, options: JournalOptions) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+
+        let snapshot_path = dir.join(SNAPSHOT);
+
+        if !snapshot_path.exists() {
+            write_atomic(&dir, SNAPSHOT, &snapshot_bytes(&Slab::<I>::new())?)?;
+        }
+
+        let snapshot = fs::read(&snapshot_path)?;
+        let mut slab = Slab::<I>::read_snapshot(&snapshot[..])?;
+        let tag = snapshot_tag(&snapshot);
+
+        let journal_path = dir.join(JOURNAL);
+        let mut journal = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&journal_path)?;
+
+        let mut buf = Vec::new();
+        journal.read_to_end(&mut buf)?;
+
+        let (records, len) = if buf.len() < HEADER || read_header::<I>(&buf)? != tag {
+            // NB: the journal belongs to an older snapshot, which means that
+            // we crashed during compaction after the new snapshot was in
+            // place. Every record in it is already part of the snapshot.
+            drop(journal);
+            write_atomic(&dir, JOURNAL, &header::<I>(tag))?;
+            journal = OpenOptions::new().append(true).open(&journal_path)?;
+            (0, HEADER)
+        } else {
+            let (records, valid) = replay(&mut slab, &buf[HEADER..])?;
+
+            if HEADER + valid < buf.len() {
+                journal.set_len((HEADER + valid) as u64)?;
+                journal.sync_all()?;
+            }
+
+            (records, HEADER + valid)
+        };
+
+        let mut this = Self {
+            slab,
+            dir,
+            journal,
+            options,
+            unsynced: 0,
+            records,
+            len: len as u64,
+            poisoned: false,
+        };
+
+        this.maybe_compact()?;
+        Ok(this)
+    }
+
+    /// Access the underlying slab allocator.
+    pub fn slab(&self) -> &Slab<I> {
+        &self.slab
+    }
+
+    /// Allocate the next id, recording it in the journal.
+    ///
+    /// An id space which has been exhausted is reported as an error of kind
+    /// [io::ErrorKind::Other] wrapping an [AllocError][crate::AllocError].
+    ///
+    /// If the record can't be written, the id is not allocated.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> io::Result<I> {
+        self.recover()?;
+        let index = self.slab.try_next().map_err(io::Error::other)?;
+
+        if let Err(e) = self.append(OP_NEXT, index) {
+            // NB: freeing the id we just allocated restores the free list.
+            self.slab.free(index);
+            return Err(e);
+        }
+
+        Ok(index)
+    }
+
+    /// Free the specified id, recording it in the journal.
+    ///
+    /// If the record can't be written, the id stays allocated.
+    pub fn free(&mut self, index: I) -> io::Result<bool> {
+        self.recover()?;
+
+        if !self.slab.free(index) {
+            return Ok(false);
+        }
+
+        if let Err(e) = self.append(OP_FREE, index) {
+            // NB: the id we just freed is at the head of the free list.
+            self.slab.next();
+            return Err(e);
+        }
+
+        Ok(true)
+    }
+
+    /// Sync any records which have not yet been synced to stable storage.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if self.unsynced > 0 {
+            self.journal.sync_data()?;
+            self.unsynced = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Write a new snapshot of the allocator and start over with an empty
+    /// journal.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let snapshot = snapshot_bytes(&self.slab)?;
+        write_atomic(&self.dir, SNAPSHOT, &snapshot)?;
+
+        // NB: once the new snapshot is in place, records appended to the old
+        // journal would be discarded when it's opened.
+        if let Err(e) = self.reset_journal(snapshot_tag(&snapshot)) {
+            self.poisoned = true;
+            return Err(e);
+        }
+
+        self.poisoned = false;
+        Ok(())
+    }
+
+    /// Replace the journal with an empty one applying to the snapshot with
+    /// the given tag.
+    fn reset_journal(&mut self, tag: [u8; 4]) -> io::Result<()> {
+        write_atomic(&self.dir, JOURNAL, &header::<I>(tag))?;
+
+        self.journal = OpenOptions::new()
+            .append(true)
+            .open(self.dir.join(JOURNAL))?;
+        self.unsynced = 0;
+        self.records = 0;
+        self.len = HEADER as u64;
+        Ok(())
+    }
+
+    /// Compact the journal if it might be out of step with the allocator.
+    ///
+    /// This must happen before the allocator is changed, since the snapshot
+    /// would otherwise include the operation being recorded.
+    fn recover(&mut self) -> io::Result<()> {
+        if self.poisoned {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Append a record to the journal.
+    ///
+    /// Once this returns successfully the record is part of the journal, and
+    /// if it fails the journal is left as it was.
+    fn append(&mut self, op: u8, index: I) -> io::Result<()> {
+        let width = usize::from(width::<I>());
+        let mut record = Vec::with_capacity(1 + width + 4);
+        record.push(op);
+        record.extend_from_slice(&index.as_u128().to_le_bytes()[..width]);
+        let crc = crc32(&record);
+        record.extend_from_slice(&crc.to_le_bytes());
+
+        if let Err(e) = self.write_record(&record) {
+            // NB: a partially written record would cause the records following
+            // it to be rejected on open, and a record which couldn't be synced
+            // would be replayed even though the operation is undone.
+            if self.journal.set_len(self.len).is_err() {
+                self.poisoned = true;
+            }
+
+            return Err(e);
+        }
+
+        self.len += record.len() as u64;
+        self.records += 1;
+
+        // NB: the record is already part of the journal, so failing to compact
+        // it doesn't fail the operation. Compaction is retried on the next
+        // append.
+        let _ = self.maybe_compact();
+        Ok(())
+    }
+
+    /// Write a record to the journal, syncing it according to the configured
+    /// policy.
+    fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        self.journal.write_all(record)?;
+        self.unsynced += 1;
+
+        match self.options.sync {
+            SyncPolicy::Always => self.sync(),
+            SyncPolicy::Every(n) if self.unsynced >= n => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compact the journal if it has grown past the configured limit.
+    fn maybe_compact(&mut self) -> io::Result<()> {
+        match self.options.compact_after {
+            Some(n) if self.records >= n => self.compact(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Serialize a snapshot of the given slab.
+fn snapshot_bytes<I>(slab: &Slab<I>) -> io::Result<Vec<u8>>
+where
+    I: Id,
+{
+    let mut buf = Vec::new();
+    slab.write_snapshot(&mut buf)?;
+    Ok(buf)
+}
+
+/// The tag identifying a snapshot, which is its trailing checksum.
+fn snapshot_tag(snapshot: &[u8]) -> [u8; 4] {
+    let mut tag = [0u8; 4];
+    tag.copy_from_slice(&snapshot[snapshot.len() - 4..]);
+    tag
+}
+
+/// Construct the header of a journal applying to the snapshot with the given
+/// tag.
+fn header<I>(tag: [u8; 4]) -> Vec<u8>
+where
+    I: Id,
+{
+    let mut buf = Vec::with_capacity(HEADER);
+    buf.extend_from_slice(&MAGIC);
+    buf.push(VERSION);
+    buf.push(width::<I>());
+    buf.extend_from_slice(&tag);
+    buf
+}
+
+/// Read the header of a journal, returning the tag of the snapshot it applies
+/// to.
+fn read_header<I>(buf: &[u8]) -> io::Result<[u8; 4]>
+where
+    I: Id,
+{
+    if buf[..MAGIC.len()] != MAGIC {
+        return Err(invalid("journal has bad magic bytes"));
+    }
+
+    if buf[MAGIC.len()] != VERSION {
+        return Err(invalid("unsupported journal version"));
+    }
+
+    if buf[MAGIC.len() + 1] != width::<I>() {
+        return Err(invalid("journal id width mismatch"));
+    }
+
+    let mut tag = [0u8; 4];
+    tag.copy_from_slice(&buf[MAGIC.len() + 2..HEADER]);
+    Ok(tag)
+}
+
+/// Replay journal records over the given slab, returning the number of
+/// records replayed and the number of bytes they occupy.
+///
+/// A final record which is truncated or fails its checksum is assumed to be
+/// the result of a crash while it was being written, and is ignored.
+fn replay<I>(slab: &mut Slab<I>, buf: &[u8]) -> io::Result<(u64, usize)>
+where
+    I: Id,
+{
+    let width = usize::from(width::<I>());
+    let size = 1 + width + 4;
+    let mut records = 0;
+    let mut offset = 0;
+
+    while buf.len() - offset >= size {
+        let record = &buf[offset..offset + size];
+        let (body, crc) = record.split_at(1 + width);
+        let mut expected = [0u8; 4];
+        expected.copy_from_slice(crc);
+
+        if crc32(body) != u32::from_le_bytes(expected) {
+            if buf.len() - offset == size {
+                break;
+            }
+
+            return Err(invalid("journal record checksum mismatch"));
+        }
+
+        let mut bytes = [0u8; 16];
+        bytes[..width].copy_from_slice(&body[1..]);
+        let index = I::from_u128(u128::from_le_bytes(bytes))
+            .ok_or_else(|| invalid("journal record id out of bounds"))?;
+
+        let ok = match body[0] {
+            OP_NEXT => slab.try_next().ok().map(|i| i.as_u128()) == Some(index.as_u128()),
+            OP_FREE => slab.free(index),
+            _ => return Err(invalid("journal record has unknown operation")),
+        };
+
+        if !ok {
+            return Err(invalid("journal record doesn't match allocator state"));
+        }
+
+        records += 1;
+        offset += size;
+    }
+
+    Ok((records, offset))
+}
+
+/// Atomically replace the file with the given name in `dir` with `contents`.
+fn write_atomic(dir: &Path, name: &str, contents: &[u8]) -> io::Result<()> {
+    let tmp = dir.join(format!("{}.tmp", name));
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, dir.join(name))?;
+
+    // NB: syncing a directory is required on some platforms to make the
+    // rename durable, and isn't supported at all on others.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
@@ -4,20 +4,316 @@
 //!
 //! * [Slab] - Allocates id in a slab-like manner, handling automatic
 //!   reclamation by keeping a record of which identifier slot to allocate next.
+//! * [TypedSlab] - A [Slab] of [TypedId]s, which are tagged with a marker
+//!   type so that ids from different allocators can't be mixed up.
+//! * [CheckedSlab] - A [Slab] which tracks the state of every slot, panicking
+//!   on double frees and frees of ids it didn't allocate.
+//! * [ArraySlab] - Like [Slab], but backed by an inline array with a fixed
+//!   number of slots, so that it works without an allocator.
+//! * [GenerationalSlab] - Like [Slab], but tags every id with a generation so
+//!   that stale ids can be detected after their slot has been reused.
+//! * [Bitmap] - Allocates ids out of a bitmap, always handing out the lowest
+//!   free id so that ids in use stay compact.
+//! * [Ida] - Allocates ids out of a radix tree of bitmaps, in the style of the
+//!   Linux IDA, using memory proportional to the number of ids in use.
+//! * [Ranges] - Allocates contiguous ranges of ids, coalescing adjacent ranges
+//!   as they are freed.
+//! * [Buddy] - Allocates power-of-two sized blocks of ids using the buddy
+//!   system, merging freed blocks with their buddies.
+//! * [AtomicSlab] - A fixed-capacity, lock-free allocator which can be shared
+//!   across threads.
+//! * [SlabMap] - A map which allocates an id for every value inserted into it,
+//!   storing the value in the slot of the id.
+//! * [SharedSlab] - A [Slab] behind a shared handle, handing out [IdGuard]s
+//!   which free their id when dropped.
+//! * [BoundedSlab] - A [Slab] with a fixed capacity, where acquiring an id
+//!   waits asynchronously for one to be freed once the capacity is reached.
+//! * [JournaledSlab] - A [Slab] which records every operation in a write-ahead
+//!   journal, so that allocations survive crashes and restarts.
 //!
 //! # Examples
 //!
-//! ```rust
+#![cfg_attr(feature = "alloc", doc = "```rust")]
+#![cfg_attr(not(feature = "alloc"), doc = "```rust,ignore")]
 //! let mut alloc = idalloc::Slab::<u32>::new();
 //! assert_eq!(0u32, alloc.next());
 //! assert_eq!(1u32, alloc.next());
 //! alloc.free(0u32);
 //! ```
-#[deny(missing_docs)]
-use std::fmt;
+//!
+//! # Features
+//!
+//! * `std` (default) - Enables the allocators which depend on the standard
+//!   library: [SharedSlab], [BoundedSlab], [JournaledSlab], [CheckedSlab] and
+//!   the snapshot methods of [Slab]. Implies `alloc`.
+//! * `alloc` - Enables the allocators which only need a global allocator, such
+//!   as [Slab]. Without it the crate only provides the [Id] trait,
+//!   [AllocError] and [ArraySlab], and can be used without any allocator at all.
+//! * `derive` - Provides `#[derive(Id)]` for newtype ids, such as
+//!   `struct EntityId(u32)`.
+//! * `serde` - Implements `Serialize` and `Deserialize` for [Slab]. The free
+//!   list is validated when deserializing, so corrupt input is rejected with an
+//!   error.
+
+#![deny(missing_docs)]
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::iter::FromIterator;
+use core::num::{NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+#[path = "private.rs"]
+pub mod __private;
+mod array;
+#[cfg(all(feature = "alloc", target_has_atomic = "64"))]
+mod atomic;
+#[cfg(feature = "alloc")]
+mod bitmap;
+#[cfg(feature = "std")]
+mod bounded;
+#[cfg(feature = "alloc")]
+mod buddy;
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(feature = "std")]
+mod checked;
+#[cfg(feature = "alloc")]
+mod generational;
+#[cfg(feature = "alloc")]
+mod ida;
+#[cfg(feature = "alloc")]
+mod iter;
+#[cfg(feature = "std")]
+mod journal;
+#[cfg(feature = "alloc")]
+mod ranges;
+#[cfg(feature = "alloc")]
+mod reserved;
+#[cfg(feature = "alloc")]
+mod reuse;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "alloc")]
+mod slab_map;
+#[cfg(feature = "std")]
+mod snapshot;
+mod typed;
+
+pub use self::array::ArraySlab;
+#[cfg(all(feature = "alloc", target_has_atomic = "64"))]
+pub use self::atomic::{AtomicId, AtomicSlab};
+#[cfg(feature = "alloc")]
+pub use self::bitmap::Bitmap;
+#[cfg(feature = "std")]
+pub use self::bounded::{Acquire, BoundedGuard, BoundedSlab};
+#[cfg(feature = "alloc")]
+pub use self::buddy::{Block, Buddy, BuddyStats};
+#[cfg(feature = "alloc")]
+pub use self::builder::SlabBuilder;
+#[cfg(feature = "std")]
+pub use self::checked::{CheckedId, CheckedSlab};
+#[cfg(feature = "alloc")]
+pub use self::generational::{GenerationalId, GenerationalSlab};
+#[cfg(feature = "alloc")]
+pub use self::ida::Ida;
+#[cfg(feature = "alloc")]
+pub use self::iter::{Drain, IterFree, IterLive};
+#[cfg(feature = "std")]
+pub use self::journal::{JournalOptions, JournaledSlab, SyncPolicy};
+#[cfg(feature = "alloc")]
+pub use self::ranges::{Fit, Ranges};
+#[cfg(feature = "alloc")]
+use self::reserved::Reserved;
+#[cfg(feature = "alloc")]
+use self::reuse::Reuse;
+#[cfg(feature = "alloc")]
+pub use self::reuse::ReusePolicy;
+#[cfg(feature = "std")]
+pub use self::shared::{IdGuard, SharedSlab};
+#[cfg(feature = "alloc")]
+pub use self::slab_map::{SlabMap, SlabMapIter, SlabMapIterMut};
+pub use self::typed::TypedId;
+#[cfg(feature = "alloc")]
+pub use self::typed::TypedSlab;
+
+/// Derive [Id][trait@Id] for a single-field tuple struct, by delegating to
+/// its field.
+///
+/// The type must also implement `Copy`, `Debug` and `Display`, which are
+/// required by [Id][trait@Id].
+///
+/// A different none sentinel than the one of the field can be picked with
+/// `#[id(none = <expr>)]`, which is supported for fields of primitive integer
+/// types. The sentinel is skipped over when allocating, so with `none = 0` the
+/// first id allocated is `1`.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "alloc", doc = "```rust")]
+#[cfg_attr(not(feature = "alloc"), doc = "```rust,ignore")]
+/// use idalloc::{Id, Slab};
+/// use std::fmt;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Id)]
+/// struct EntityId(u32);
+///
+/// impl fmt::Display for EntityId {
+///     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         self.0.fmt(fmt)
+///     }
+/// }
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Id)]
+/// #[id(none = 0)]
+/// struct Handle(u32);
+///
+/// impl fmt::Display for Handle {
+///     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         self.0.fmt(fmt)
+///     }
+/// }
+///
+/// let mut alloc = Slab::<EntityId>::new();
+/// assert_eq!(EntityId(0), alloc.next());
+/// assert!(EntityId::none().is_none());
+///
+/// let mut alloc = Slab::<Handle>::new();
+/// assert_eq!(Handle(1), alloc.next());
+/// assert_eq!(Handle(2), alloc.next());
+/// assert_eq!(Handle(0), Handle::none());
+/// ```
+///
+/// Ranges of ids handed out by [Ranges] and [Buddy] skip over a custom
+/// sentinel, so they can span it:
+///
+#[cfg_attr(feature = "alloc", doc = "```rust")]
+#[cfg_attr(not(feature = "alloc"), doc = "```rust,ignore")]
+/// use idalloc::{Buddy, Id, Ranges};
+/// use std::fmt;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Id)]
+/// #[id(none = 5)]
+/// struct Small(u8);
+///
+/// impl fmt::Display for Small {
+///     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         self.0.fmt(fmt)
+///     }
+/// }
+///
+/// let mut alloc = Ranges::<Small>::new();
+/// assert!(alloc.alloc_range(255).is_err());
+/// assert_eq!(Ok(Small(0)..Small(4)), alloc.alloc_range(4));
+/// assert_eq!(Ok(Small(4)..Small(8)), alloc.alloc_range(3));
+/// assert_eq!(Ok(Small(8)..Small(255)), alloc.alloc_range(247));
+///
+/// let mut alloc = Buddy::<Small>::new();
+/// let block = alloc.alloc_order(alloc.max_order()).unwrap();
+/// assert_eq!(Small(0)..Small(129), block.range());
+/// ```
+///
+/// Negative sentinels other than `-1` aren't supported, since negative values
+/// aren't ids of signed types:
+///
+/// ```rust,compile_fail
+/// use idalloc::Id;
+/// use std::fmt;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Id)]
+/// #[id(none = -5)]
+/// struct Signed(i32);
+///
+/// impl fmt::Display for Signed {
+///     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         self.0.fmt(fmt)
+///     }
+/// }
+///
+/// let _ = Signed::none();
+/// ```
+#[cfg(feature = "derive")]
+pub use idalloc_derive::Id;
 
 /// A type that can be used an allocator index.
+///
+/// This is implemented for:
+/// * Unsigned integers, where the maximum value is the none sentinel.
+/// * Signed integers, where `-1` is the none sentinel. Other negative values
+///   aren't ids either, and map to the same index as `-1`, so allocators
+///   treat them like the none sentinel.
+/// * The `NonZero*` integers, where the maximum value is the none sentinel.
+///   These keep the niche of the underlying type, so an `Option` of them is
+///   no larger than the id itself.
+///
+/// Every id maps to a zero-based index through [Id::as_usize] and
+/// [Id::as_u128], where the none sentinel has the largest index. So the index
+/// of a `NonZeroU32` is one less than its value, and the index of `-1` is one
+/// past the maximum value of the signed type.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "alloc", doc = "```rust")]
+#[cfg_attr(not(feature = "alloc"), doc = "```rust,ignore")]
+/// use idalloc::Slab;
+/// use std::mem::size_of;
+/// use std::num::NonZeroU32;
+///
+/// let mut alloc = Slab::<NonZeroU32>::new();
+/// let id = alloc.next();
+/// assert_eq!(1, id.get());
+/// assert_eq!(size_of::<u32>(), size_of::<Option<NonZeroU32>>());
+///
+/// let mut alloc = Slab::<i32>::new();
+/// assert_eq!(0, alloc.next());
+/// ```
 pub trait Id: Copy + fmt::Display + fmt::Debug {
+    /// The initial, unallocated value.
+    ///
+    /// This is the same as [Id::initial], but can be used in constant
+    /// expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id;
+    ///
+    /// const INITIAL: u16 = <u16 as Id>::INITIAL;
+    /// assert_eq!(0, INITIAL);
+    /// ```
+    const INITIAL: Self;
+
+    /// The none sentinel value.
+    ///
+    /// This is the same as [Id::none], but can be used in constant
+    /// expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id;
+    ///
+    /// const NONE: u16 = <u16 as Id>::NONE;
+    /// assert_eq!(u16::MAX, NONE);
+    /// ```
+    const NONE: Self;
+
     /// Allocate the initial, unallocated value.
     ///
     /// # Examples
@@ -28,7 +324,10 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
     /// assert_eq!(0, u16::initial());
     /// assert_eq!(0, u16::initial());
     /// ```
-    fn initial() -> Self;
+    #[inline(always)]
+    fn initial() -> Self {
+        Self::INITIAL
+    }
 
     /// Get the index as a usize.
     ///
@@ -37,11 +336,63 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
     /// ```rust
     /// use idalloc::Id as _;
     ///
+    /// use std::num::NonZeroU32;
+    ///
     /// assert_eq!(42, 42u16.as_usize());
     /// assert_eq!(42, 42u32.as_usize());
+    /// assert_eq!(0, NonZeroU32::new(1).unwrap().as_usize());
+    /// assert_eq!(128, (-1i8).as_usize());
+    /// assert_eq!(128, (-5i8).as_usize());
     /// ```
     fn as_usize(self) -> usize;
 
+    /// Construct the index from a usize, or `None` if it can't be
+    /// represented or is the none sentinel value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id as _;
+    ///
+    /// use std::num::NonZeroU8;
+    ///
+    /// assert_eq!(Some(42u8), u8::from_usize(42));
+    /// assert_eq!(None, u8::from_usize(255));
+    /// assert_eq!(None, u8::from_usize(256));
+    /// assert_eq!(Some(127i8), i8::from_usize(127));
+    /// assert_eq!(None, i8::from_usize(128));
+    /// assert_eq!(NonZeroU8::new(1), NonZeroU8::from_usize(0));
+    /// assert_eq!(None, NonZeroU8::from_usize(254));
+    /// ```
+    fn from_usize(index: usize) -> Option<Self>;
+
+    /// Get the index as a u128, which can hold any index without truncation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id as _;
+    ///
+    /// assert_eq!(42, 42u8.as_u128());
+    /// assert_eq!(u128::MAX, u128::none().as_u128());
+    /// assert_eq!(1 << 127, i128::none().as_u128());
+    /// ```
+    fn as_u128(self) -> u128;
+
+    /// Construct the index from a u128, or `None` if it can't be represented
+    /// or is the none sentinel value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id as _;
+    ///
+    /// assert_eq!(Some(42u64), u64::from_u128(42));
+    /// assert_eq!(None, u64::from_u128(u64::MAX as u128));
+    /// assert_eq!(None, u64::from_u128(u128::MAX));
+    /// ```
+    fn from_u128(index: u128) -> Option<Self>;
+
     /// Increment the index and return the incremented value.
     ///
     /// # Examples
@@ -54,6 +405,26 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
     /// ```
     fn increment(self) -> Self;
 
+    /// Increment the index and return the incremented value, or `None` if the
+    /// index can't be incremented any further.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id as _;
+    ///
+    /// assert_eq!(Some(1), 0u8.checked_increment());
+    /// assert_eq!(None, u8::none().checked_increment());
+    /// ```
+    #[inline(always)]
+    fn checked_increment(self) -> Option<Self> {
+        if self.is_none() {
+            return None;
+        }
+
+        Some(self.increment())
+    }
+
     /// Take the value and replace the existing value with the none variant.
     ///
     /// # Examples
@@ -80,6 +451,26 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
     /// ```
     fn expect(self, m: &str) -> Self;
 
+    /// Convert the value into an option, where the none sentinel value is
+    /// mapped to `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Id as _;
+    ///
+    /// assert_eq!(Some(1u32), 1u32.into_option());
+    /// assert_eq!(None, u32::none().into_option());
+    /// ```
+    #[inline(always)]
+    fn into_option(self) -> Option<Self> {
+        if self.is_none() {
+            return None;
+        }
+
+        Some(self)
+    }
+
     /// Construct the none sentinel value for this type.
     ///
     /// # Examples
@@ -89,7 +480,10 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
     ///
     /// assert!(u32::none().is_none());
     /// ```
-    fn none() -> Self;
+    #[inline(always)]
+    fn none() -> Self {
+        Self::NONE
+    }
 
     /// Test if the value is the none sentinel value.
     ///
@@ -106,20 +500,33 @@ pub trait Id: Copy + fmt::Display + fmt::Debug {
 macro_rules! impl_primitive_index {
     ($ty:ident) => {
         impl Id for $ty {
-            #[inline(always)]
-            fn initial() -> Self {
-                0
-            }
+            const INITIAL: Self = 0;
+            const NONE: Self = $ty::MAX;
 
             #[inline(always)]
             fn as_usize(self) -> usize {
                 self as usize
             }
 
+            #[inline(always)]
+            fn from_usize(index: usize) -> Option<Self> {
+                $ty::try_from(index).ok()?.into_option()
+            }
+
+            #[inline(always)]
+            fn as_u128(self) -> u128 {
+                self as u128
+            }
+
+            #[inline(always)]
+            fn from_u128(index: u128) -> Option<Self> {
+                $ty::try_from(index).ok()?.into_option()
+            }
+
             #[inline(always)]
             fn increment(self) -> Self {
                 if self.is_none() {
-                    panic!("index `{}` is out of bounds: 0-{}", self, std::$ty::MAX);
+                    panic!("index `{}` is out of bounds: 0-{}", self, $ty::MAX);
                 }
 
                 self + 1
@@ -127,7 +534,7 @@ macro_rules! impl_primitive_index {
 
             #[inline(always)]
             fn take(&mut self) -> Self {
-                std::mem::replace(self, Self::none())
+                core::mem::replace(self, Self::none())
             }
 
             #[inline(always)]
@@ -140,8 +547,142 @@ macro_rules! impl_primitive_index {
             }
 
             #[inline(always)]
-            fn none() -> Self {
-                std::$ty::MAX
+            fn is_none(self) -> bool {
+                self == Self::none()
+            }
+        }
+    };
+}
+
+macro_rules! impl_signed_index {
+    ($ty:ident) => {
+        impl Id for $ty {
+            const INITIAL: Self = 0;
+            const NONE: Self = -1;
+
+            #[inline(always)]
+            fn as_usize(self) -> usize {
+                usize::try_from(self.as_u128()).unwrap_or(usize::MAX)
+            }
+
+            #[inline(always)]
+            fn from_usize(index: usize) -> Option<Self> {
+                $ty::try_from(index).ok()
+            }
+
+            #[inline(always)]
+            fn as_u128(self) -> u128 {
+                // NB: negative values aren't ids, so they all map to the index
+                // of the none sentinel.
+                if self < 0 {
+                    return $ty::MAX as u128 + 1;
+                }
+
+                self as u128
+            }
+
+            #[inline(always)]
+            fn from_u128(index: u128) -> Option<Self> {
+                $ty::try_from(index).ok()
+            }
+
+            #[inline(always)]
+            fn increment(self) -> Self {
+                match self.checked_increment() {
+                    Some(index) => index,
+                    None => panic!("index `{}` is out of bounds: 0-{}", self, $ty::MAX),
+                }
+            }
+
+            #[inline(always)]
+            fn checked_increment(self) -> Option<Self> {
+                match self {
+                    -1 => None,
+                    // NB: the none sentinel comes after the maximum value.
+                    $ty::MAX => Some(Self::none()),
+                    index => Some(index + 1),
+                }
+            }
+
+            #[inline(always)]
+            fn take(&mut self) -> Self {
+                core::mem::replace(self, Self::none())
+            }
+
+            #[inline(always)]
+            fn expect(self, m: &str) -> Self {
+                if self.is_none() {
+                    panic!("{}", m);
+                }
+
+                self
+            }
+
+            #[inline(always)]
+            fn is_none(self) -> bool {
+                self == Self::none()
+            }
+        }
+    };
+}
+
+macro_rules! impl_non_zero_index {
+    ($ty:ident, $inner:ident) => {
+        impl Id for $ty {
+            const INITIAL: Self = $ty::MIN;
+            const NONE: Self = $ty::MAX;
+
+            #[inline(always)]
+            fn as_usize(self) -> usize {
+                (self.get() - 1) as usize
+            }
+
+            #[inline(always)]
+            fn from_usize(index: usize) -> Option<Self> {
+                let value = $inner::try_from(index).ok()?.checked_add(1)?;
+                $ty::new(value)?.into_option()
+            }
+
+            #[inline(always)]
+            fn as_u128(self) -> u128 {
+                (self.get() - 1) as u128
+            }
+
+            #[inline(always)]
+            fn from_u128(index: u128) -> Option<Self> {
+                let value = $inner::try_from(index).ok()?.checked_add(1)?;
+                $ty::new(value)?.into_option()
+            }
+
+            #[inline(always)]
+            fn increment(self) -> Self {
+                match self.checked_increment() {
+                    Some(index) => index,
+                    None => panic!("index `{}` is out of bounds: 1-{}", self, $ty::MAX),
+                }
+            }
+
+            #[inline(always)]
+            fn checked_increment(self) -> Option<Self> {
+                if self.is_none() {
+                    return None;
+                }
+
+                self.checked_add(1)
+            }
+
+            #[inline(always)]
+            fn take(&mut self) -> Self {
+                core::mem::replace(self, Self::none())
+            }
+
+            #[inline(always)]
+            fn expect(self, m: &str) -> Self {
+                if self.is_none() {
+                    panic!("{}", m);
+                }
+
+                self
             }
 
             #[inline(always)]
@@ -157,6 +698,188 @@ impl_primitive_index!(u16);
 impl_primitive_index!(u32);
 impl_primitive_index!(u64);
 impl_primitive_index!(u128);
+impl_primitive_index!(usize);
+
+impl_signed_index!(i8);
+impl_signed_index!(i16);
+impl_signed_index!(i32);
+impl_signed_index!(i64);
+impl_signed_index!(i128);
+impl_signed_index!(isize);
+
+impl_non_zero_index!(NonZeroU8, u8);
+impl_non_zero_index!(NonZeroU16, u16);
+impl_non_zero_index!(NonZeroU32, u32);
+impl_non_zero_index!(NonZeroU64, u64);
+impl_non_zero_index!(NonZeroU128, u128);
+impl_non_zero_index!(NonZeroUsize, usize);
+
+/// Error raised when an allocator fails to allocate an id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocError {
+    /// The id space of the allocator has been exhausted.
+    Exhausted,
+    /// The free list of the allocator is corrupt, since it refers to a slot
+    /// which is already allocated.
+    Corrupted {
+        /// The index of the slot that the free list referred to.
+        index: usize,
+    },
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exhausted => write!(fmt, "id space exhausted"),
+            Self::Corrupted { index } => {
+                write!(fmt, "free list corrupted: slot `{}` is not free", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocError {}
+
+/// Error raised by [Slab::allocate_at] when the id can't be allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocateAtError {
+    /// The id is already allocated.
+    AlreadyAllocated {
+        /// The index of the id.
+        index: usize,
+    },
+    /// The id is reserved through [SlabBuilder::reserve] or
+    /// [SlabBuilder::start_at].
+    Reserved {
+        /// The index of the id.
+        index: usize,
+    },
+    /// The id is the none sentinel, or above the maximum id set through
+    /// [SlabBuilder::max].
+    OutOfRange {
+        /// The index of the id.
+        index: usize,
+    },
+}
+
+impl fmt::Display for AllocateAtError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyAllocated { index } => {
+                write!(fmt, "id with index `{}` is already allocated", index)
+            }
+            Self::Reserved { index } => write!(fmt, "id with index `{}` is reserved", index),
+            Self::OutOfRange { index } => {
+                write!(fmt, "id with index `{}` is out of range", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocateAtError {}
+
+/// Error describing the first inconsistency found by [Slab::validate].
+///
+/// Links are described by the id of the slot they are stored in, where `None`
+/// is the head of the free list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The free list links past the first unused slot.
+    OutOfBounds {
+        /// The slot storing the link.
+        from: Option<usize>,
+        /// The index the link refers to.
+        to: usize,
+        /// The id of the first unused slot, which the free list is expected
+        /// to end at. Without reserved ids, this is the number of slots.
+        len: usize,
+    },
+    /// The free list links to a slot which has already been visited, so it
+    /// has a cycle.
+    Cycle {
+        /// The slot storing the link.
+        from: usize,
+        /// The slot the link refers to.
+        to: usize,
+    },
+    /// The free list links to a slot which is allocated.
+    Allocated {
+        /// The slot storing the link.
+        from: Option<usize>,
+        /// The slot the link refers to.
+        to: usize,
+    },
+    /// The number of slots in the free list and the number of allocated ids
+    /// don't add up to the number of slots, so some slots are unaccounted for.
+    CountMismatch {
+        /// The number of slots in the free list.
+        free: usize,
+        /// The number of allocated slots.
+        live: usize,
+        /// The number of slots in the slab.
+        len: usize,
+    },
+    /// The reserved ranges of ids overlap, aren't sorted, or reach past the
+    /// maximum id, or the maximum id is past the none sentinel.
+    InvalidConfig,
+    /// The slab has more slots than there are ids which can be handed out.
+    TooManySlots {
+        /// The number of slots in the slab.
+        len: usize,
+        /// The number of ids which can be handed out.
+        capacity: usize,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Link(Option<usize>);
+
+        impl fmt::Display for Link {
+            fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0 {
+                    Some(slot) => write!(fmt, "slot `{}`", slot),
+                    None => write!(fmt, "the head of the free list"),
+                }
+            }
+        }
+
+        match *self {
+            Self::OutOfBounds { from, to, len } => write!(
+                fmt,
+                "{} links out of bounds to `{}`, expected `{}`",
+                Link(from),
+                to,
+                len
+            ),
+            Self::Cycle { from, to } => write!(
+                fmt,
+                "free list has a cycle, slot `{}` links back to slot `{}`",
+                from, to
+            ),
+            Self::Allocated { from, to } => write!(
+                fmt,
+                "{} links to slot `{}`, which is allocated",
+                Link(from),
+                to
+            ),
+            Self::CountMismatch { free, live, len } => write!(
+                fmt,
+                "{} free and {} allocated slots don't add up to {} slots",
+                free, live, len
+            ),
+            Self::InvalidConfig => write!(fmt, "invalid reserved ids or maximum id"),
+            Self::TooManySlots { len, capacity } => {
+                write!(fmt, "{} slots exceed the capacity of {} ids", len, capacity)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
 
 /// A slab-based id allocator which can deal with automatic reclamation as ids
 /// are [freed][Slab::free].
@@ -181,14 +904,31 @@ impl_primitive_index!(u128);
 /// assert_eq!(0, alloc.next());
 /// assert_eq!(3, alloc.next());
 /// ```
+#[cfg(feature = "alloc")]
 pub struct Slab<I>
 where
     I: Id,
 {
     data: Vec<I>,
     next: I,
+    /// The number of allocated ids.
+    live: usize,
+    /// The last slot in the free list, which is only meaningful while the free
+    /// list isn't empty.
+    tail: I,
+    /// The previous slot of every slot in the free list, where the head of the
+    /// free list links to the none sentinel. This is only built once a slot
+    /// is unlinked from the middle of the free list by [Slab::allocate_at].
+    prev: Option<Vec<I>>,
+    /// Ids which are never handed out, and don't take up any slots.
+    reserved: Reserved,
+    /// One past the largest index which can be allocated.
+    limit: usize,
+    /// The state of the policy deciding which freed id to reuse next.
+    reuse: Reuse,
 }
 
+#[cfg(feature = "alloc")]
 impl<I> Slab<I>
 where
     I: Id,
@@ -212,11 +952,281 @@ where
         Self {
             data: Vec::new(),
             next: I::initial(),
+            live: 0,
+            tail: I::none(),
+            prev: None,
+            reserved: Reserved::default(),
+            limit: I::none().as_usize(),
+            reuse: Reuse::Lifo,
         }
     }
 
+    /// Construct an empty slab allocator out of its raw configuration, which
+    /// is validated first.
+    #[cfg(any(feature = "std", feature = "serde"))]
+    pub(crate) fn from_config(
+        reserved: Vec<Range<usize>>,
+        limit: usize,
+        policy: ReusePolicy,
+    ) -> Result<Self, ValidationError> {
+        let reserved = Reserved::new(reserved).ok_or(ValidationError::InvalidConfig)?;
+
+        if limit > I::none().as_usize() || reserved.last().is_some_and(|last| last.end >= limit) {
+            return Err(ValidationError::InvalidConfig);
+        }
+
+        Ok(Self::with_config(reserved, limit, policy))
+    }
+
+    /// Replace the slots and the head of the free list of an empty slab
+    /// allocator with the given raw parts, which are validated first.
+    ///
+    /// Since the history of freed ids isn't part of the raw parts, ids
+    /// quarantined by [ReusePolicy::Quarantine] can be reused right away.
+    #[cfg(any(feature = "std", feature = "serde"))]
+    pub(crate) fn with_parts(mut self, data: Vec<I>, next: I) -> Result<Self, ValidationError> {
+        if data.len() > self.capacity() {
+            return Err(ValidationError::TooManySlots {
+                len: data.len(),
+                capacity: self.capacity(),
+            });
+        }
+
+        self.data = data;
+        self.next = next;
+
+        let (free, tail) = self.walk()?;
+        self.live = self.data.len() - free;
+
+        if let Some(tail) = tail.and_then(I::from_usize) {
+            self.tail = tail;
+        }
+
+        if self.reuse_policy() == ReusePolicy::LowestFirst {
+            let free = self.iter_free().collect::<Vec<_>>();
+
+            for index in free {
+                let slot = self.slot(index);
+                self.reuse.linked(slot);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Construct a slab allocator which never hands out the given ranges of
+    /// ids, or ids from `limit` and up, and reuses ids according to `policy`.
+    fn with_config(reserved: Reserved, limit: usize, policy: ReusePolicy) -> Self {
+        let mut slab = Self {
+            reserved,
+            limit,
+            reuse: Reuse::new(policy),
+            ..Self::new()
+        };
+
+        slab.next = slab.first_unused();
+        slab
+    }
+
+    /// Construct a slab allocator where the given ids are allocated, and the
+    /// ids below the largest one which aren't are free, like the
+    /// [FromIterator] implementation. Duplicate ids are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [AllocateAtError::OutOfRange] if any of the ids is the none
+    /// sentinel.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocateAtError, Slab};
+    ///
+    /// let mut alloc = Slab::try_from_iter(vec![2u8, 0, 2])?;
+    /// assert_eq!(1, alloc.next());
+    ///
+    /// let e = Slab::try_from_iter(vec![2u8, u8::MAX]).err();
+    /// assert_eq!(Some(AllocateAtError::OutOfRange { index: 255 }), e);
+    /// # Ok::<_, AllocateAtError>(())
+    /// ```
+    pub fn try_from_iter<T>(iter: T) -> Result<Self, AllocateAtError>
+    where
+        T: IntoIterator<Item = I>,
+    {
+        let mut ids = iter.into_iter().collect::<Vec<_>>();
+        ids.sort_unstable_by_key(|id| id.as_usize());
+
+        let mut slab = Self::new();
+
+        // NB: in increasing order every id either grows the slab or is a
+        // duplicate, so the free list never has to be unlinked from.
+        for id in ids {
+            match slab.allocate_at(id) {
+                Ok(()) | Err(AllocateAtError::AlreadyAllocated { .. }) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(slab)
+    }
+
+    /// Get the number of allocated ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let id = alloc.next();
+    /// alloc.next();
+    /// assert_eq!(2, alloc.len());
+    /// alloc.free(id);
+    /// assert_eq!(1, alloc.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Test if no ids are allocated.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Get the number of freed ids which are waiting to be reused.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let id = alloc.next();
+    /// alloc.next();
+    /// assert_eq!(0, alloc.free_len());
+    /// alloc.free(id);
+    /// assert_eq!(1, alloc.free_len());
+    /// ```
+    pub fn free_len(&self) -> usize {
+        self.data.len() - self.live
+    }
+
+    /// Get the maximum number of ids which can be allocated at a time, which
+    /// is limited by the number of ids `I` can represent, and by the ids
+    /// which are reserved or above the maximum set through [SlabBuilder].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// assert_eq!(255, Slab::<u8>::new().capacity());
+    /// assert_eq!(65535, Slab::<u16>::new().capacity());
+    /// assert_eq!(10, Slab::<u16>::builder().start_at(1).max(10).build().capacity());
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.limit - self.reserved_below(self.limit)
+    }
+
+    /// Get the number of slots in use by the allocator, which is the number
+    /// of distinct ids handed out so far, not counting reserved ids.
+    ///
+    /// Freed ids are usually reused before new ones are handed out, which
+    /// makes this the largest number of ids which have been allocated at the
+    /// same time. That isn't the case for ids which are held back by
+    /// [ReusePolicy::Quarantine], or which were skipped over by
+    /// [Slab::allocate_at].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{ReusePolicy, Slab};
+    ///
+    /// let mut alloc = Slab::<u32>::new();
+    /// let a = alloc.next();
+    /// let b = alloc.next();
+    /// alloc.free(a);
+    /// alloc.free(b);
+    /// alloc.next();
+    /// assert_eq!(2, alloc.high_water_mark());
+    ///
+    /// let mut alloc = Slab::<u32>::builder().reuse(ReusePolicy::Quarantine(10)).build();
+    /// let a = alloc.next();
+    /// alloc.free(a);
+    /// alloc.next();
+    /// assert_eq!(2, alloc.high_water_mark());
+    /// ```
+    pub fn high_water_mark(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Test if the given id is currently allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut alloc = idalloc::Slab::<u32>::new();
+    /// let id = alloc.next();
+    /// assert!(alloc.is_allocated(id));
+    /// assert!(!alloc.is_allocated(id + 1));
+    /// alloc.free(id);
+    /// assert!(!alloc.is_allocated(id));
+    /// ```
+    pub fn is_allocated(&self, index: I) -> bool {
+        if self.is_reserved(index.as_usize()) {
+            return false;
+        }
+
+        match self.data.get(self.slot(index)) {
+            // NB: once every id has been handed out, the tail of the free list
+            // holds the none sentinel just like an allocated slot.
+            Some(entry) => entry.is_none() && !(self.has_free() && self.is_tail(index)),
+            None => false,
+        }
+    }
+
+    /// Test if there are any freed ids waiting to be reused.
+    fn has_free(&self) -> bool {
+        self.data.len() > self.live
+    }
+
+    /// Test if the given id is the tail of the free list.
+    fn is_tail(&self, index: I) -> bool {
+        self.tail.as_usize() == index.as_usize()
+    }
+
+    /// Test if the given index is reserved.
+    fn is_reserved(&self, index: usize) -> bool {
+        self.reserved.contains(index)
+    }
+
+    /// Count the reserved indexes below `index`.
+    fn reserved_below(&self, index: usize) -> usize {
+        self.reserved.below(index)
+    }
+
+    /// Get the slot of the given id, which must not be reserved.
+    fn slot(&self, index: I) -> usize {
+        let index = index.as_usize();
+        index - self.reserved_below(index)
+    }
+
+    /// Get the id of the first slot which hasn't been used yet, which the tail
+    /// of the free list links to.
+    fn first_unused(&self) -> I {
+        // NB: once every id has been handed out, this is the none sentinel.
+        I::from_usize(self.reserved.id_at(self.data.len())).unwrap_or_else(I::none)
+    }
+
+    /// Test if every id that `I` can represent has been handed out, in which
+    /// case the tail of the free list links to the none sentinel.
+    fn is_full(&self) -> bool {
+        self.first_unused().is_none()
+    }
+
     /// Allocate the next id.
     ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See [Slab::try_next] for a
+    /// fallible alternative.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -224,17 +1234,101 @@ where
     /// assert_eq!(0u32, alloc.next());
     /// assert_eq!(1u32, alloc.next());
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> I {
+        match self.try_next() {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to allocate the next id, returning an error instead of panicking
+    /// if that is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Slab};
+    ///
+    /// let mut alloc = Slab::<u8>::new();
+    ///
+    /// for n in 0..u8::MAX {
+    ///     assert_eq!(Ok(n), alloc.try_next());
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// alloc.free(42);
+    /// assert_eq!(Ok(42), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// ```
+    pub fn try_next(&mut self) -> Result<I, AllocError> {
+        if let Some(index) = self.pop_lowest() {
+            self.unlink(index);
+            self.live += 1;
+            self.reuse.allocated();
+            return Ok(index);
+        }
+
+        if self.has_free() && !self.reuse.is_ready(self.slot(self.next)) {
+            let index = self.first_unused();
+
+            if index.as_usize() >= self.limit {
+                return Err(AllocError::Exhausted);
+            }
+
+            self.grow_to(index);
+            self.live += 1;
+            self.reuse.allocated();
+            return Ok(index);
+        }
+
         let index = self.next;
+        let full = self.is_full();
+        let slot = self.slot(index);
 
-        self.next = if let Some(entry) = self.data.get_mut(self.next.as_usize()) {
-            entry.take().expect("next index is null")
+        self.next = if let Some(entry) = self.data.get_mut(slot) {
+            match entry.take().into_option() {
+                Some(next) => next,
+                None if full => I::none(),
+                None => {
+                    return Err(AllocError::Corrupted {
+                        index: index.as_usize(),
+                    })
+                }
+            }
         } else {
+            if index.as_usize() >= self.limit {
+                return Err(AllocError::Exhausted);
+            }
+
             self.data.push(I::none());
-            self.next.increment()
+
+            if let Some(prev) = &mut self.prev {
+                prev.push(I::none());
+            }
+
+            self.first_unused()
         };
 
-        index
+        self.link_prev(self.next, I::none());
+        self.live += 1;
+        self.reuse.allocated();
+        Ok(index)
+    }
+
+    /// Pop the lowest freed id, if ids are reused lowest first.
+    fn pop_lowest(&mut self) -> Option<I> {
+        while let Some(slot) = self.reuse.pop_lowest() {
+            let index = I::from_usize(self.reserved.id_at(slot))?;
+
+            // NB: the slot might since have been allocated through
+            // `allocate_at`.
+            if !self.is_allocated(index) {
+                return Some(index);
+            }
+        }
+
+        None
     }
 
     /// Free the specified id.
@@ -249,18 +1343,308 @@ where
     /// assert!(!alloc.free(id));
     /// ```
     pub fn free(&mut self, index: I) -> bool {
-        if let Some(entry) = self.data.get_mut(index.as_usize()) {
-            if entry.is_none() {
-                *entry = self.next;
-                self.next = index;
-                return true;
+        if !self.is_allocated(index) {
+            return false;
+        }
+
+        let slot = self.slot(index);
+
+        if self.reuse.appends() && self.has_free() {
+            let tail = self.tail;
+            let tail_slot = self.slot(tail);
+            self.link_prev(index, tail);
+            self.data[slot] = self.data[tail_slot];
+            self.data[tail_slot] = index;
+            self.tail = index;
+        } else {
+            if !self.has_free() {
+                self.tail = index;
+            }
+
+            self.link_prev(self.next, index);
+            self.link_prev(index, I::none());
+            self.data[slot] = self.next;
+            self.next = index;
+        }
+
+        self.live -= 1;
+        self.reuse.freed(slot);
+        true
+    }
+
+    /// Allocate the specified id, which is useful to restore the state of an
+    /// allocator from ids which are in use elsewhere.
+    ///
+    /// Ids below the specified one which haven't been handed out yet are
+    /// added to the free list.
+    ///
+    /// Allocating an id in the middle of the free list requires finding the
+    /// slot before it. So the first time this happens, links back through the
+    /// free list are built and maintained from then on, which makes it a
+    /// constant time operation at the cost of one extra id per slot.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the id is already allocated, if it is reserved, or if it is
+    /// the none sentinel or above the maximum id set through [SlabBuilder].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocateAtError, Slab};
+    ///
+    /// let mut alloc = Slab::<u32>::new();
+    /// assert_eq!(Ok(()), alloc.allocate_at(2));
+    /// assert_eq!(Err(AllocateAtError::AlreadyAllocated { index: 2 }), alloc.allocate_at(2));
+    /// assert_eq!(Ok(()), alloc.allocate_at(0));
+    ///
+    /// assert_eq!(1, alloc.next());
+    /// assert_eq!(3, alloc.next());
+    ///
+    /// let mut alloc = Slab::<u8>::builder().start_at(1).max(10).build();
+    /// assert_eq!(Err(AllocateAtError::Reserved { index: 0 }), alloc.allocate_at(0));
+    /// assert_eq!(Err(AllocateAtError::OutOfRange { index: 11 }), alloc.allocate_at(11));
+    /// assert_eq!(Err(AllocateAtError::OutOfRange { index: 255 }), alloc.allocate_at(255));
+    /// assert_eq!(Ok(()), alloc.allocate_at(10));
+    /// ```
+    pub fn allocate_at(&mut self, index: I) -> Result<(), AllocateAtError> {
+        if index.is_none() || index.as_usize() >= self.limit {
+            return Err(AllocateAtError::OutOfRange {
+                index: index.as_usize(),
+            });
+        }
+
+        if self.is_reserved(index.as_usize()) {
+            return Err(AllocateAtError::Reserved {
+                index: index.as_usize(),
+            });
+        }
+
+        if self.slot(index) < self.data.len() {
+            if self.is_allocated(index) {
+                return Err(AllocateAtError::AlreadyAllocated {
+                    index: index.as_usize(),
+                });
+            }
+
+            self.unlink(index);
+        } else {
+            self.grow_to(index);
+        }
+
+        self.live += 1;
+        self.reuse.allocated();
+        Ok(())
+    }
+
+    /// Unlink the given free slot from the free list and mark it as
+    /// allocated.
+    fn unlink(&mut self, index: I) {
+        let mut prev = match self.prev.take() {
+            Some(prev) => prev,
+            None => self.back_links(),
+        };
+
+        let slot = self.slot(index);
+        let before = prev[slot];
+        let after = self.data[slot];
+
+        if before.is_none() {
+            self.next = after;
+        } else {
+            let before = self.slot(before);
+            self.data[before] = after;
+        }
+
+        if let Some(link) = prev.get_mut(self.slot(after)) {
+            *link = before;
+        }
+
+        if self.is_tail(index) {
+            self.tail = before;
+        }
+
+        self.data[slot] = I::none();
+        self.prev = Some(prev);
+    }
+
+    /// Grow the slab so that the slot of the given id is the last one and
+    /// allocated, adding any slots in between to the end of the free list.
+    fn grow_to(&mut self, index: I) {
+        // The id holding the link to the first unused slot, where `None` is
+        // the head of the free list.
+        let mut before = if self.has_free() {
+            Some(self.tail)
+        } else {
+            None
+        };
+
+        for slot in self.data.len()..self.slot(index) {
+            let current = I::from_usize(self.reserved.id_at(slot)).unwrap_or_else(I::none);
+            self.set_link(before, current);
+            // NB: linked on the next iteration, or once the slab has grown.
+            self.data.push(I::none());
+
+            if let Some(prev) = &mut self.prev {
+                prev.push(before.unwrap_or_else(I::none));
             }
+
+            self.reuse.linked(slot);
+            before = Some(current);
+        }
+
+        self.data.push(I::none());
+
+        if let Some(prev) = &mut self.prev {
+            prev.push(I::none());
+        }
+
+        let last = self.first_unused();
+        self.set_link(before, last);
+
+        if let Some(before) = before {
+            self.tail = before;
         }
+    }
 
-        false
+    /// Set the link stored in the slot of the given id, where `None` is the
+    /// head of the free list.
+    fn set_link(&mut self, index: Option<I>, link: I) {
+        match index {
+            Some(index) => {
+                let slot = self.slot(index);
+                self.data[slot] = link;
+            }
+            None => self.next = link,
+        }
+    }
+
+    /// Build the links back through the free list.
+    fn back_links(&self) -> Vec<I> {
+        let mut prev = vec![I::none(); self.data.len()];
+        let mut before = I::none();
+        let mut current = self.next;
+
+        while let Some(link) = prev.get_mut(self.slot(current)) {
+            *link = before;
+            before = current;
+            current = self.data[self.slot(current)];
+        }
+
+        prev
+    }
+
+    /// Update the link back from the slot of the given id, if links back
+    /// through the free list are maintained and the slot is in bounds.
+    fn link_prev(&mut self, index: I, before: I) {
+        let slot = self.slot(index);
+
+        if let Some(link) = self.prev.as_mut().and_then(|prev| prev.get_mut(slot)) {
+            *link = before;
+        }
+    }
+
+    /// Check that the allocator is internally consistent.
+    ///
+    /// This walks the free list and makes sure that it is acyclic, that it
+    /// only links to free slots, and that every slot is either allocated or
+    /// reachable through it. The first problem found is returned as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// let mut alloc = Slab::<u8>::new();
+    ///
+    /// for _ in 0..u8::MAX {
+    ///     alloc.next();
+    /// }
+    ///
+    /// assert!(alloc.free(3));
+    /// assert!(!alloc.free(3));
+    /// assert_eq!(Ok(()), alloc.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let (free, _) = self.walk()?;
+        let len = self.data.len();
+
+        if free + self.live != len {
+            return Err(ValidationError::CountMismatch {
+                free,
+                live: self.live,
+                len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Walk the free list and check that it is consistent with the slots,
+    /// returning the number of free slots and the last slot in the free list.
+    fn walk(&self) -> Result<(usize, Option<usize>), ValidationError> {
+        let len = self.data.len();
+        let full = self.is_full();
+        let end = self.first_unused().as_usize();
+        let mut visited = vec![false; len];
+        let mut free = 0;
+        let mut from = None;
+        let mut current = self.next;
+
+        loop {
+            let index = current.as_usize();
+
+            if self.is_reserved(index) {
+                return Err(ValidationError::Allocated { from, to: index });
+            }
+
+            let slot = self.slot(current);
+
+            if slot >= len {
+                break;
+            }
+
+            if visited[slot] {
+                return Err(ValidationError::Cycle {
+                    from: from.unwrap_or(index),
+                    to: index,
+                });
+            }
+
+            if self.data[slot].is_none() && !full {
+                return Err(ValidationError::Allocated { from, to: index });
+            }
+
+            visited[slot] = true;
+            free += 1;
+            from = Some(index);
+            current = self.data[slot];
+        }
+
+        if current.as_usize() != end {
+            return Err(ValidationError::OutOfBounds {
+                from,
+                to: current.as_usize(),
+                len: end,
+            });
+        }
+
+        let live = self
+            .data
+            .iter()
+            .zip(&visited)
+            .filter(|(entry, visited)| entry.is_none() && !**visited)
+            .count();
+
+        if free + live != len {
+            return Err(ValidationError::CountMismatch { free, live, len });
+        }
+
+        Ok((free, from))
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<I> Default for Slab<I>
 where
     I: Id,
@@ -269,3 +1653,41 @@ where
         Self::new()
     }
 }
+
+/// Construct a slab allocator where the given ids are allocated, and the ids
+/// below the largest one which aren't are free. Duplicate ids are ignored.
+///
+/// Free ids are reused lowest first.
+///
+/// # Panics
+///
+/// Panics if any of the ids is the none sentinel. See [Slab::try_from_iter]
+/// for a fallible alternative.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::Slab;
+///
+/// let mut alloc = vec![4u32, 1, 4].into_iter().collect::<Slab<_>>();
+/// assert_eq!(2, alloc.len());
+/// assert_eq!(0, alloc.next());
+/// assert_eq!(2, alloc.next());
+/// assert_eq!(3, alloc.next());
+/// assert_eq!(5, alloc.next());
+/// ```
+#[cfg(feature = "alloc")]
+impl<I> FromIterator<I> for Slab<I>
+where
+    I: Id,
+{
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = I>,
+    {
+        match Self::try_from_iter(iter) {
+            Ok(slab) => slab,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
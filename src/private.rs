@@ -0,0 +1,73 @@
+//! Helpers used by the code generated by `#[derive(Id)]`.
+//!
+//! A custom none sentinel is skipped over when mapping values to indexes, so
+//! that indexes stay contiguous and the sentinel maps to the largest index,
+//! which is the index of the none sentinel of the underlying type.
+
+use crate::Id;
+use core::convert::TryFrom;
+
+/// Convert an index into a usize, saturating if it doesn't fit.
+pub fn as_usize(index: u128) -> usize {
+    usize::try_from(index).unwrap_or(usize::MAX)
+}
+
+/// Get the index of `value`, given the custom sentinel `none`.
+pub fn as_u128<T>(value: T, none: T) -> u128
+where
+    T: Id,
+{
+    let index = value.as_u128();
+    let none = none.as_u128();
+
+    if index == none {
+        T::none().as_u128()
+    } else if index > none {
+        index - 1
+    } else {
+        index
+    }
+}
+
+/// Get the value with the given index, given the custom sentinel `none`.
+pub fn from_u128<T>(index: u128, none: T) -> Option<T>
+where
+    T: Id,
+{
+    let max = T::none().as_u128();
+
+    if index >= max {
+        return None;
+    }
+
+    let index = if index >= none.as_u128() {
+        index + 1
+    } else {
+        index
+    };
+
+    if index == max {
+        return Some(T::none());
+    }
+
+    T::from_u128(index)
+}
+
+/// Get the value following `value`, given the custom sentinel `none`.
+pub fn checked_increment<T>(value: T, none: T) -> Option<T>
+where
+    T: Id,
+{
+    let index = as_u128(value, none).checked_add(1)?;
+    let max = T::none().as_u128();
+
+    if index > max {
+        return None;
+    }
+
+    if index == max {
+        return Some(none);
+    }
+
+    from_u128(index, none)
+}
@@ -0,0 +1,286 @@
+use crate::{AllocError, Id};
+use alloc::collections::BTreeMap;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+/// The policy used by [Ranges] to pick which free interval to allocate from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    /// Allocate from the lowest free interval which is large enough.
+    #[default]
+    First,
+    /// Allocate from the smallest free interval which is large enough,
+    /// preferring lower ids on ties.
+    Best,
+}
+
+/// Get the exclusive end of the ids managed by [Ranges].
+///
+/// This is the largest id rather than the none sentinel, since the sentinel
+/// doesn't follow every id for signed ids or custom sentinels, so it can't be
+/// used as the end of a range.
+#[inline(always)]
+fn end<I>() -> u128
+where
+    I: Id,
+{
+    I::none().as_u128() - 1
+}
+
+/// Convert a bound back into an id.
+///
+/// Bounds never exceed [end], so they can always be represented.
+#[inline(always)]
+fn to_id<I>(value: u128) -> I
+where
+    I: Id,
+{
+    I::from_u128(value).unwrap_or_else(I::none)
+}
+
+/// An allocator for contiguous ranges of ids.
+///
+/// Free ids are kept as a set of disjoint intervals. Freeing a range
+/// coalesces it with any adjacent free intervals, so that the id space doesn't
+/// fragment more than necessary.
+///
+/// The largest id is never handed out, so that it can be used as the
+/// exclusive end of a range which reaches the end of the id space.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::Ranges;
+///
+/// let mut alloc = Ranges::<u32>::new();
+/// assert_eq!(Ok(0..4), alloc.alloc_range(4));
+/// assert_eq!(Ok(4..6), alloc.alloc_range(2));
+/// assert!(alloc.free(0..4));
+/// assert_eq!(Ok(0..3), alloc.alloc_range(3));
+/// assert_eq!(Ok(6..10), alloc.alloc_range(4));
+/// ```
+pub struct Ranges<I>
+where
+    I: Id,
+{
+    /// Free intervals, mapping the start of an interval to its exclusive end.
+    free: BTreeMap<u128, u128>,
+    fit: Fit,
+    _marker: PhantomData<I>,
+}
+
+impl<I> Ranges<I>
+where
+    I: Id,
+{
+    /// Construct a new range allocator using the [first fit][Fit::First]
+    /// policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Ranges;
+    ///
+    /// let mut alloc = Ranges::<u8>::new();
+    /// assert_eq!(Ok(0..254), alloc.alloc_range(254));
+    ///
+    /// // The largest id is the end of the last range, since the none
+    /// // sentinel of signed ids is -1.
+    /// let mut alloc = Ranges::<i8>::new();
+    /// assert_eq!(Ok(0..100), alloc.alloc_range(100));
+    /// assert_eq!(Ok(100..127), alloc.alloc_range(27));
+    /// assert!(alloc.alloc_range(1).is_err());
+    ///
+    /// let mut alloc = Ranges::<i32>::new();
+    /// assert_eq!(Ok(0..i32::MAX), alloc.alloc_range(i32::MAX as usize));
+    /// ```
+    pub fn new() -> Self {
+        Self::with_fit(Fit::default())
+    }
+
+    /// Construct a new range allocator using the given fit policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{Fit, Ranges};
+    ///
+    /// let mut alloc = Ranges::<u32>::with_fit(Fit::Best);
+    /// let a = alloc.alloc_range(4).unwrap();
+    /// let _ = alloc.alloc_range(1).unwrap();
+    /// let b = alloc.alloc_range(2).unwrap();
+    /// let _ = alloc.alloc_range(1).unwrap();
+    /// alloc.free(a);
+    /// alloc.free(b);
+    ///
+    /// // The two id gap is the best fit, even though the four id gap comes
+    /// // first.
+    /// assert_eq!(Ok(5..7), alloc.alloc_range(2));
+    /// ```
+    pub fn with_fit(fit: Fit) -> Self {
+        let mut free = BTreeMap::new();
+        free.insert(I::initial().as_u128(), end::<I>());
+
+        Self {
+            free,
+            fit,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allocate a range of `len` contiguous ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, Ranges};
+    ///
+    /// let mut alloc = Ranges::<u8>::new();
+    /// assert_eq!(Ok(0..200), alloc.alloc_range(200));
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.alloc_range(100));
+    /// assert_eq!(Ok(200..254), alloc.alloc_range(54));
+    /// ```
+    pub fn alloc_range(&mut self, len: usize) -> Result<Range<I>, AllocError> {
+        self.alloc_range_aligned(len, 1)
+    }
+
+    /// Allocate a range of `len` contiguous ids, where the first id is a
+    /// multiple of `align`.
+    ///
+    /// A `len` of zero always succeeds with an empty range, and leaves the
+    /// allocator unmodified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Ranges;
+    ///
+    /// let mut alloc = Ranges::<u32>::new();
+    /// assert_eq!(Ok(0..3), alloc.alloc_range(3));
+    /// assert_eq!(Ok(8..12), alloc.alloc_range_aligned(4, 8));
+    /// assert_eq!(Ok(3..8), alloc.alloc_range(5));
+    ///
+    /// let mut alloc = Ranges::<u32>::new();
+    /// assert_eq!(Ok(0..3), alloc.alloc_range(3));
+    /// assert_eq!(Ok(0..0), alloc.alloc_range_aligned(0, 8));
+    /// assert!(alloc.free(0..3));
+    /// assert_eq!(Ok(0..12), alloc.alloc_range(12));
+    /// ```
+    pub fn alloc_range_aligned(
+        &mut self,
+        len: usize,
+        align: usize,
+    ) -> Result<Range<I>, AllocError> {
+        if align == 0 {
+            panic!("alignment must be non-zero");
+        }
+
+        // NB: an empty range would otherwise split the interval it fits in
+        // into two adjacent intervals, which are never coalesced again.
+        if len == 0 {
+            return Ok(to_id(0)..to_id(0));
+        }
+
+        let len = len as u128;
+        let align = align as u128;
+        let mut found = None::<(u128, u128, u128)>;
+
+        for (&start, &end) in &self.free {
+            let aligned = match start.checked_add((align - start % align) % align) {
+                Some(aligned) => aligned,
+                None => continue,
+            };
+
+            match aligned.checked_add(len) {
+                Some(stop) if stop <= end => (),
+                _ => continue,
+            }
+
+            match self.fit {
+                Fit::First => {
+                    found = Some((start, end, aligned));
+                    break;
+                }
+                Fit::Best => match found {
+                    Some((s, e, _)) if e - s <= end - start => (),
+                    _ => found = Some((start, end, aligned)),
+                },
+            }
+        }
+
+        let (start, end, aligned) = found.ok_or(AllocError::Exhausted)?;
+        self.free.remove(&start);
+
+        if start < aligned {
+            self.free.insert(start, aligned);
+        }
+
+        if aligned + len < end {
+            self.free.insert(aligned + len, end);
+        }
+
+        Ok(to_id(aligned)..to_id(aligned + len))
+    }
+
+    /// Free the specified range of ids, which can be all or part of a range
+    /// which was previously allocated.
+    ///
+    /// Returns `false` and leaves the allocator unmodified if any id in the
+    /// range is not allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Ranges;
+    ///
+    /// let mut alloc = Ranges::<u32>::new();
+    /// let range = alloc.alloc_range(10).unwrap();
+    /// assert!(alloc.free(2..4));
+    /// assert!(!alloc.free(3..5));
+    /// assert!(alloc.free(4..6));
+    /// assert_eq!(Ok(2..6), alloc.alloc_range(4));
+    /// assert!(alloc.free(range));
+    /// assert_eq!(Ok(0..20), alloc.alloc_range(20));
+    /// ```
+    pub fn free(&mut self, range: Range<I>) -> bool {
+        let mut start = range.start.as_u128();
+        let mut end = range.end.as_u128();
+
+        if start >= end || end > self::end::<I>() {
+            return false;
+        }
+
+        // NB: free intervals are disjoint, so only the last one starting
+        // before the end of the range can overlap with it.
+        if let Some((&s, &e)) = self.free.range(..end).next_back() {
+            if e > start {
+                return false;
+            }
+
+            if e == start {
+                self.free.remove(&s);
+                start = s;
+            }
+        }
+
+        if let Some(e) = self.free.remove(&end) {
+            end = e;
+        }
+
+        self.free.insert(start, end);
+        true
+    }
+}
+
+impl<I> Default for Ranges<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A range of reserved ids, along with the number of reserved ids below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    range: Range<usize>,
+    below: usize,
+}
+
+impl Entry {
+    /// The slot of the first id following the range.
+    fn slot(&self) -> usize {
+        self.range.start - self.below
+    }
+}
+
+/// Sorted, non-adjacent ranges of ids which are never handed out by a
+/// [Slab][crate::Slab].
+///
+/// Reserved ids don't take up any slots, so the slot of an id is the id less
+/// the number of reserved ids below it. The number of reserved ids below
+/// every range is stored, so that ids and slots can be mapped to each other
+/// with a binary search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Reserved {
+    entries: Vec<Entry>,
+}
+
+impl Reserved {
+    /// Construct reserved ranges out of ranges which are sorted, non-empty
+    /// and non-adjacent, or `None` if they aren't.
+    pub(crate) fn new<T>(ranges: T) -> Option<Self>
+    where
+        T: IntoIterator<Item = Range<usize>>,
+    {
+        let mut entries = Vec::<Entry>::new();
+        let mut below = 0;
+
+        for range in ranges {
+            if range.start >= range.end {
+                return None;
+            }
+
+            if let Some(last) = entries.last() {
+                if range.start <= last.range.end {
+                    return None;
+                }
+            }
+
+            let len = range.end - range.start;
+            entries.push(Entry { range, below });
+            below += len;
+        }
+
+        Some(Self { entries })
+    }
+
+    /// Iterate over the reserved ranges, in increasing order.
+    pub(crate) fn iter(&self) -> impl ExactSizeIterator<Item = &Range<usize>> + '_ {
+        self.entries.iter().map(|entry| &entry.range)
+    }
+
+    /// Get the last reserved range.
+    pub(crate) fn last(&self) -> Option<&Range<usize>> {
+        Some(&self.entries.last()?.range)
+    }
+
+    /// Test if the given id is reserved.
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        let n = self
+            .entries
+            .partition_point(|entry| entry.range.end <= index);
+
+        match self.entries.get(n) {
+            Some(entry) => entry.range.start <= index,
+            None => false,
+        }
+    }
+
+    /// Count the reserved ids below the given id.
+    pub(crate) fn below(&self, index: usize) -> usize {
+        let n = self
+            .entries
+            .partition_point(|entry| entry.range.start < index);
+
+        match n.checked_sub(1).map(|n| &self.entries[n]) {
+            Some(entry) => entry.below + entry.range.end.min(index) - entry.range.start,
+            None => 0,
+        }
+    }
+
+    /// Get the id stored in the given slot.
+    pub(crate) fn id_at(&self, slot: usize) -> usize {
+        let n = self.entries.partition_point(|entry| entry.slot() <= slot);
+
+        match n.checked_sub(1).map(|n| &self.entries[n]) {
+            Some(entry) => slot + entry.below + (entry.range.end - entry.range.start),
+            None => slot,
+        }
+    }
+}
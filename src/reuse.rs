@@ -0,0 +1,182 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// The order in which a [Slab][crate::Slab] reuses freed ids, configured
+/// through [SlabBuilder::reuse][crate::SlabBuilder::reuse].
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::{ReusePolicy, Slab};
+///
+/// let mut alloc = Slab::<u32>::builder().reuse(ReusePolicy::Fifo).build();
+/// let a = alloc.next();
+/// let b = alloc.next();
+/// alloc.free(a);
+/// alloc.free(b);
+/// assert_eq!(a, alloc.next());
+/// assert_eq!(b, alloc.next());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReusePolicy {
+    /// Reuse the most recently freed id first. This is the fastest policy,
+    /// and the default.
+    #[default]
+    Lifo,
+    /// Reuse the least recently freed id first, so that it takes as long as
+    /// possible before an id is reused.
+    Fifo,
+    /// Reuse the lowest freed id first, which keeps the allocated ids compact.
+    /// This keeps a heap of freed ids and links back through the free list.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{ReusePolicy, Slab};
+    ///
+    /// let mut alloc = Slab::<u32>::builder().reuse(ReusePolicy::LowestFirst).build();
+    ///
+    /// for _ in 0..4 {
+    ///     alloc.next();
+    /// }
+    ///
+    /// alloc.free(2);
+    /// alloc.free(0);
+    /// alloc.free(3);
+    /// assert_eq!(0, alloc.next());
+    /// assert_eq!(2, alloc.next());
+    /// assert_eq!(3, alloc.next());
+    /// ```
+    LowestFirst,
+    /// Reuse the least recently freed id first, but only once the given
+    /// number of other allocations and frees have happened since it was
+    /// freed. New ids are handed out in the meantime, and if there are none
+    /// left allocation fails with [AllocError::Exhausted][crate::AllocError::Exhausted].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{ReusePolicy, Slab};
+    ///
+    /// let mut alloc = Slab::<u32>::builder().reuse(ReusePolicy::Quarantine(2)).build();
+    /// let a = alloc.next();
+    /// alloc.free(a);
+    /// assert_eq!(1, alloc.next());
+    /// assert_eq!(2, alloc.next());
+    /// assert_eq!(a, alloc.next());
+    /// ```
+    Quarantine(u64),
+}
+
+/// The state needed to implement a [ReusePolicy].
+pub(crate) enum Reuse {
+    Lifo,
+    Fifo,
+    LowestFirst {
+        /// Freed slots, some of which might since have been allocated through
+        /// [Slab::allocate_at][crate::Slab::allocate_at].
+        heap: BinaryHeap<Reverse<usize>>,
+    },
+    Quarantine {
+        delay: u64,
+        /// The number of allocations and frees so far.
+        ops: u64,
+        /// The value of `ops` when each slot was last freed, or zero if it
+        /// never was.
+        freed_at: Vec<u64>,
+    },
+}
+
+impl Reuse {
+    /// Construct the state for the given policy.
+    pub(crate) fn new(policy: ReusePolicy) -> Self {
+        match policy {
+            ReusePolicy::Lifo => Self::Lifo,
+            ReusePolicy::Fifo => Self::Fifo,
+            ReusePolicy::LowestFirst => Self::LowestFirst {
+                heap: BinaryHeap::new(),
+            },
+            ReusePolicy::Quarantine(delay) => Self::Quarantine {
+                delay,
+                ops: 0,
+                freed_at: Vec::new(),
+            },
+        }
+    }
+
+    /// Get the policy implemented.
+    pub(crate) fn policy(&self) -> ReusePolicy {
+        match *self {
+            Self::Lifo => ReusePolicy::Lifo,
+            Self::Fifo => ReusePolicy::Fifo,
+            Self::LowestFirst { .. } => ReusePolicy::LowestFirst,
+            Self::Quarantine { delay, .. } => ReusePolicy::Quarantine(delay),
+        }
+    }
+
+    /// Test if freed slots are appended to the free list instead of being
+    /// pushed to the front of it.
+    pub(crate) fn appends(&self) -> bool {
+        matches!(self, Self::Fifo | Self::Quarantine { .. })
+    }
+
+    /// Record that a slot has been allocated.
+    pub(crate) fn allocated(&mut self) {
+        if let Self::Quarantine { ops, .. } = self {
+            *ops += 1;
+        }
+    }
+
+    /// Record that a slot has been added to the free list without being
+    /// freed, since it was skipped over when the slab grew.
+    pub(crate) fn linked(&mut self, slot: usize) {
+        if let Self::LowestFirst { heap } = self {
+            heap.push(Reverse(slot));
+        }
+    }
+
+    /// Record that a slot has been freed.
+    pub(crate) fn freed(&mut self, slot: usize) {
+        match self {
+            Self::LowestFirst { heap } => {
+                heap.push(Reverse(slot));
+            }
+            Self::Quarantine { ops, freed_at, .. } => {
+                *ops += 1;
+
+                if freed_at.len() <= slot {
+                    freed_at.resize(slot + 1, 0);
+                }
+
+                freed_at[slot] = *ops;
+            }
+            _ => {}
+        }
+    }
+
+    /// Test if the given freed slot can be reused yet.
+    pub(crate) fn is_ready(&self, slot: usize) -> bool {
+        match self {
+            Self::Quarantine {
+                delay,
+                ops,
+                freed_at,
+            } => match freed_at.get(slot) {
+                Some(&at) if at != 0 => *ops - at >= *delay,
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    /// Pop the lowest slot which might be free, if the policy is
+    /// [ReusePolicy::LowestFirst].
+    pub(crate) fn pop_lowest(&mut self) -> Option<usize> {
+        match self {
+            Self::LowestFirst { heap } => Some(heap.pop()?.0),
+            _ => None,
+        }
+    }
+}
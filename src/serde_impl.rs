@@ -0,0 +1,123 @@
+//! Serde support for allocators, enabled through the `serde` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use idalloc::Slab;
+//!
+//! let mut alloc = Slab::<u32>::new();
+//! alloc.next();
+//! alloc.next();
+//! alloc.free(0);
+//!
+//! let json = serde_json::to_string(&alloc).unwrap();
+//! let mut alloc: Slab<u32> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(0, alloc.next());
+//! assert_eq!(2, alloc.next());
+//!
+//! // Free lists which have cycles, link out of bounds, or end in an allocated
+//! // slot are rejected.
+//! assert!(serde_json::from_str::<Slab<u32>>(r#"{"data":[0],"next":0}"#).is_err());
+//! assert!(serde_json::from_str::<Slab<u32>>(r#"{"data":[5],"next":0}"#).is_err());
+//! assert!(serde_json::from_str::<Slab<u32>>(r#"{"data":[4294967295],"next":0}"#).is_err());
+//! assert!(serde_json::from_str::<Slab<u32>>(r#"{"data":[],"next":1}"#).is_err());
+//! ```
+//!
+//! Reserved ids, the maximum id and the reuse policy are preserved:
+//!
+//! ```rust
+//! use idalloc::{AllocError, ReusePolicy, Slab};
+//!
+//! let mut alloc = Slab::<u32>::builder()
+//!     .reserve(1..3)
+//!     .max(4)
+//!     .reuse(ReusePolicy::Fifo)
+//!     .build();
+//!
+//! assert_eq!(0, alloc.next());
+//! assert_eq!(3, alloc.next());
+//! alloc.free(0);
+//! alloc.free(3);
+//!
+//! let json = serde_json::to_string(&alloc).unwrap();
+//! let mut alloc: Slab<u32> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(ReusePolicy::Fifo, alloc.reuse_policy());
+//! assert_eq!(Ok(0), alloc.try_next());
+//! assert_eq!(Ok(3), alloc.try_next());
+//! assert_eq!(Ok(4), alloc.try_next());
+//! assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+//!
+//! // Reserved ranges which overlap or reach past the maximum id are rejected.
+//! let json = r#"{"data":[],"next":4294967295,"reserved":[{"start":0,"end":8}],"limit":4}"#;
+//! assert!(serde_json::from_str::<Slab<u32>>(json).is_err());
+//! ```
+
+use crate::reserved::Reserved;
+use crate::{Id, ReusePolicy, Slab};
+use alloc::vec::Vec;
+use core::ops::Range;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+impl<I> Serialize for Slab<I>
+where
+    I: Id + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Slab", 5)?;
+        s.serialize_field("data", &self.data)?;
+        s.serialize_field("next", &self.next)?;
+        s.serialize_field("reserved", &self.reserved)?;
+        s.serialize_field("limit", &self.limit)?;
+        s.serialize_field("reuse", &self.reuse_policy())?;
+        s.end()
+    }
+}
+
+impl Serialize for Reserved {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, I> Deserialize<'de> for Slab<I>
+where
+    I: Id + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Slab")]
+        struct Repr<I> {
+            data: Vec<I>,
+            next: I,
+            #[serde(default)]
+            reserved: Vec<Range<usize>>,
+            #[serde(default)]
+            limit: Option<usize>,
+            #[serde(default)]
+            reuse: ReusePolicy,
+        }
+
+        let Repr {
+            data,
+            next,
+            reserved,
+            limit,
+            reuse,
+        } = Repr::<I>::deserialize(deserializer)?;
+
+        let limit = limit.unwrap_or_else(|| I::none().as_usize());
+        Slab::from_config(reserved, limit, reuse)
+            .and_then(|slab| slab.with_parts(data, next))
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{AllocError, Id, Slab};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A [Slab] behind a shared handle, which hands out [IdGuard]s that free
+/// their id automatically when dropped.
+///
+/// Cloning a shared slab produces another handle to the same allocator.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::SharedSlab;
+///
+/// let alloc = SharedSlab::<u32>::new();
+///
+/// let a = alloc.acquire();
+/// let b = alloc.acquire();
+/// assert_eq!(0, a.id());
+/// assert_eq!(1, b.id());
+///
+/// drop(a);
+/// assert_eq!(0, alloc.acquire().id());
+/// ```
+pub struct SharedSlab<I>
+where
+    I: Id,
+{
+    slab: Arc<Mutex<Slab<I>>>,
+}
+
+impl<I> SharedSlab<I>
+where
+    I: Id,
+{
+    /// Construct a new shared slab allocator.
+    pub fn new() -> Self {
+        Self {
+            slab: Arc::new(Mutex::new(Slab::new())),
+        }
+    }
+
+    /// Acquire an id, which is freed when the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See
+    /// [SharedSlab::try_acquire] for a fallible alternative.
+    pub fn acquire(&self) -> IdGuard<I> {
+        match self.try_acquire() {
+            Ok(guard) => guard,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to acquire an id, returning an error instead of panicking if that
+    /// is not possible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, SharedSlab};
+    ///
+    /// let alloc = SharedSlab::<u8>::new();
+    /// let guards = (0..u8::MAX).map(|_| alloc.acquire()).collect::<Vec<_>>();
+    /// assert_eq!(Some(AllocError::Exhausted), alloc.try_acquire().err());
+    ///
+    /// drop(guards);
+    /// assert!(alloc.try_acquire().is_ok());
+    /// ```
+    pub fn try_acquire(&self) -> Result<IdGuard<I>, AllocError> {
+        let id = self.lock().try_next()?;
+
+        Ok(IdGuard {
+            slab: Some(self.slab.clone()),
+            id,
+        })
+    }
+
+    /// Lock the underlying slab.
+    ///
+    /// Since the slab is never left in an inconsistent state by a panic, a
+    /// poisoned lock is ignored.
+    fn lock(&self) -> MutexGuard<'_, Slab<I>> {
+        self.slab.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<I> Clone for SharedSlab<I>
+where
+    I: Id,
+{
+    fn clone(&self) -> Self {
+        Self {
+            slab: self.slab.clone(),
+        }
+    }
+}
+
+impl<I> Default for SharedSlab<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An id acquired from a [SharedSlab], which is freed when the guard is
+/// dropped.
+pub struct IdGuard<I>
+where
+    I: Id,
+{
+    /// The slab to free the id in, which is `None` once the id has been
+    /// converted into a raw id.
+    slab: Option<Arc<Mutex<Slab<I>>>>,
+    id: I,
+}
+
+impl<I> IdGuard<I>
+where
+    I: Id,
+{
+    /// Get the id held by this guard.
+    pub fn id(&self) -> I {
+        self.id
+    }
+
+    /// Convert the guard into its raw id without freeing it.
+    ///
+    /// The id can be turned back into a guard with [IdGuard::from_raw], or it
+    /// will remain allocated forever.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{IdGuard, SharedSlab};
+    ///
+    /// let alloc = SharedSlab::<u32>::new();
+    /// let id = alloc.acquire().into_raw();
+    /// assert_eq!(1, alloc.acquire().id());
+    ///
+    /// drop(IdGuard::from_raw(&alloc, id));
+    /// assert_eq!(0, alloc.acquire().id());
+    /// ```
+    pub fn into_raw(mut self) -> I {
+        self.slab = None;
+        self.id
+    }
+
+    /// Construct a guard out of a raw id previously returned by
+    /// [IdGuard::into_raw], which will free the id when dropped.
+    ///
+    /// The id must have been allocated by the given slab and not already be
+    /// owned by another guard, or it will be freed twice. Freeing an id twice
+    /// is detected by the slab and has no effect, but the id might already
+    /// have been handed out again in between.
+    pub fn from_raw(slab: &SharedSlab<I>, id: I) -> Self {
+        Self {
+            slab: Some(slab.slab.clone()),
+            id,
+        }
+    }
+}
+
+impl<I> fmt::Debug for IdGuard<I>
+where
+    I: Id,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("IdGuard").field(&self.id).finish()
+    }
+}
+
+impl<I> Drop for IdGuard<I>
+where
+    I: Id,
+{
+    fn drop(&mut self) {
+        if let Some(slab) = self.slab.take() {
+            let mut slab = slab.lock().unwrap_or_else(PoisonError::into_inner);
+            slab.free(self.id);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{AllocError, Id};
+use alloc::vec::Vec;
+use core::iter::Enumerate;
+use core::mem;
+use core::ops::{Index, IndexMut};
+use core::slice;
+
+/// An entry in a [SlabMap].
+enum Entry<I, T> {
+    /// An occupied entry holding a value.
+    Occupied(T),
+    /// A vacant entry, linking to the next vacant entry in the free list.
+    Vacant(I),
+}
+
+/// A map which allocates an id for every value inserted into it, in the same
+/// manner as a [Slab][crate::Slab].
+///
+/// Vacant slots are threaded into an intrusive free list, just like in a
+/// [Slab][crate::Slab], so they take up no more space than an occupied slot.
+///
+/// # Examples
+///
+/// ```rust
+/// use idalloc::SlabMap;
+///
+/// let mut map = SlabMap::<u32, &str>::new();
+/// let a = map.insert("a");
+/// let b = map.insert("b");
+/// assert_eq!("a", map[a]);
+/// assert_eq!(Some("a"), map.remove(a));
+/// assert_eq!(None, map.get(a));
+///
+/// let c = map.insert("c");
+/// assert_eq!(a, c);
+/// assert_eq!(vec![(0, &"c"), (1, &"b")], map.iter().collect::<Vec<_>>());
+/// ```
+pub struct SlabMap<I, T>
+where
+    I: Id,
+{
+    entries: Vec<Entry<I, T>>,
+    next: I,
+    len: usize,
+}
+
+impl<I, T> SlabMap<I, T>
+where
+    I: Id,
+{
+    /// Construct a new, empty slab map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let map = SlabMap::<u32, String>::new();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            next: I::initial(),
+            len: 0,
+        }
+    }
+
+    /// Get the number of values in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// let id = map.insert(1);
+    /// map.insert(2);
+    /// assert_eq!(2, map.len());
+    /// map.remove(id);
+    /// assert_eq!(1, map.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value, returning the id allocated for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id space has been exhausted. See [SlabMap::try_insert]
+    /// for a fallible alternative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// assert_eq!(0, map.insert(42));
+    /// assert_eq!(1, map.insert(42));
+    /// ```
+    pub fn insert(&mut self, value: T) -> I {
+        match self.try_insert(value) {
+            Ok(index) => index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Try to insert a value, returning an error instead of panicking if no id
+    /// could be allocated for it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, SlabMap};
+    ///
+    /// let mut map = SlabMap::<u8, ()>::new();
+    ///
+    /// for n in 0..u8::MAX {
+    ///     assert_eq!(Ok(n), map.try_insert(()));
+    /// }
+    ///
+    /// assert_eq!(Err(AllocError::Exhausted), map.try_insert(()));
+    /// map.remove(42);
+    /// assert_eq!(Ok(42), map.try_insert(()));
+    /// ```
+    pub fn try_insert(&mut self, value: T) -> Result<I, AllocError> {
+        let index = self.next;
+
+        self.next = if let Some(entry) = self.entries.get_mut(index.as_usize()) {
+            let next = match entry {
+                Entry::Vacant(next) => *next,
+                Entry::Occupied(..) => {
+                    return Err(AllocError::Corrupted {
+                        index: index.as_usize(),
+                    })
+                }
+            };
+
+            *entry = Entry::Occupied(value);
+            next
+        } else {
+            let next = index.checked_increment().ok_or(AllocError::Exhausted)?;
+            self.entries.push(Entry::Occupied(value));
+            next
+        };
+
+        self.len += 1;
+        Ok(index)
+    }
+
+    /// Test if the map contains a value with the given id.
+    pub fn contains(&self, index: I) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Get a reference to the value with the given id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// let id = map.insert(42);
+    /// assert_eq!(Some(&42), map.get(id));
+    /// assert_eq!(None, map.get(id + 1));
+    /// ```
+    pub fn get(&self, index: I) -> Option<&T> {
+        match self.entries.get(index.as_usize())? {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant(..) => None,
+        }
+    }
+
+    /// Get a mutable reference to the value with the given id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// let id = map.insert(42);
+    /// *map.get_mut(id).unwrap() += 1;
+    /// assert_eq!(43, map[id]);
+    /// ```
+    pub fn get_mut(&mut self, index: I) -> Option<&mut T> {
+        match self.entries.get_mut(index.as_usize())? {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant(..) => None,
+        }
+    }
+
+    /// Remove the value with the given id, freeing the id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// let id = map.insert(42);
+    /// assert_eq!(Some(42), map.remove(id));
+    /// assert_eq!(None, map.remove(id));
+    /// ```
+    pub fn remove(&mut self, index: I) -> Option<T> {
+        let entry = self.entries.get_mut(index.as_usize())?;
+
+        let value = match mem::replace(entry, Entry::Vacant(self.next)) {
+            Entry::Occupied(value) => value,
+            vacant => {
+                *entry = vacant;
+                return None;
+            }
+        };
+
+        self.next = index;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Iterate over the ids and values in the map, in order of their ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, char>::new();
+    /// map.insert('a');
+    /// let b = map.insert('b');
+    /// map.insert('c');
+    /// map.remove(b);
+    ///
+    /// assert_eq!(vec![(0, &'a'), (2, &'c')], map.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn iter(&self) -> SlabMapIter<'_, I, T> {
+        SlabMapIter {
+            entries: self.entries.iter().enumerate(),
+        }
+    }
+
+    /// Iterate mutably over the ids and values in the map, in order of their
+    /// ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::SlabMap;
+    ///
+    /// let mut map = SlabMap::<u32, u32>::new();
+    /// map.insert(1);
+    /// map.insert(2);
+    ///
+    /// for (_, value) in map.iter_mut() {
+    ///     *value *= 10;
+    /// }
+    ///
+    /// assert_eq!(vec![(0, &10), (1, &20)], map.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn iter_mut(&mut self) -> SlabMapIterMut<'_, I, T> {
+        SlabMapIterMut {
+            entries: self.entries.iter_mut().enumerate(),
+        }
+    }
+}
+
+impl<I, T> Default for SlabMap<I, T>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, T> Index<I> for SlabMap<I, T>
+where
+    I: Id,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        match self.get(index) {
+            Some(value) => value,
+            None => panic!("no value with id `{}`", index),
+        }
+    }
+}
+
+impl<I, T> IndexMut<I> for SlabMap<I, T>
+where
+    I: Id,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        match self.get_mut(index) {
+            Some(value) => value,
+            None => panic!("no value with id `{}`", index),
+        }
+    }
+}
+
+impl<'a, I, T> IntoIterator for &'a SlabMap<I, T>
+where
+    I: Id,
+{
+    type Item = (I, &'a T);
+    type IntoIter = SlabMapIter<'a, I, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, I, T> IntoIterator for &'a mut SlabMap<I, T>
+where
+    I: Id,
+{
+    type Item = (I, &'a mut T);
+    type IntoIter = SlabMapIterMut<'a, I, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the ids and values in a [SlabMap], created by
+/// [SlabMap::iter].
+pub struct SlabMapIter<'a, I, T> {
+    entries: Enumerate<slice::Iter<'a, Entry<I, T>>>,
+}
+
+impl<'a, I, T> Iterator for SlabMapIter<'a, I, T>
+where
+    I: Id,
+{
+    type Item = (I, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, entry) in &mut self.entries {
+            if let Entry::Occupied(value) = entry {
+                return Some((I::from_usize(index)?, value));
+            }
+        }
+
+        None
+    }
+}
+
+/// A mutable iterator over the ids and values in a [SlabMap], created by
+/// [SlabMap::iter_mut].
+pub struct SlabMapIterMut<'a, I, T> {
+    entries: Enumerate<slice::IterMut<'a, Entry<I, T>>>,
+}
+
+impl<'a, I, T> Iterator for SlabMapIterMut<'a, I, T>
+where
+    I: Id,
+{
+    type Item = (I, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, entry) in &mut self.entries {
+            if let Entry::Occupied(value) = entry {
+                return Some((I::from_usize(index)?, value));
+            }
+        }
+
+        None
+    }
+}
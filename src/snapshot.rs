@@ -0,0 +1,459 @@
+use crate::{Id, ReusePolicy, Slab};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::vec;
+use std::vec::Vec;
+
+/// Magic bytes at the start of every snapshot.
+const MAGIC: [u8; 4] = *b"IDAL";
+/// The current version of the snapshot format.
+const VERSION: u8 = 2;
+/// The allocator kind of a [Slab] snapshot.
+const KIND_SLAB: u8 = 1;
+
+/// Construct an error indicating that a snapshot is malformed.
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The width of `I` in bytes.
+pub(crate) fn width<I>() -> u8
+where
+    I: Id,
+{
+    ((128 - I::none().as_u128().leading_zeros()) / 8) as u8
+}
+
+/// Compute the CRC-32 (IEEE) checksum of the given bytes.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &b in bytes {
+        crc ^= u32::from(b);
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+/// Write a LEB128-encoded varint.
+fn write_varint(out: &mut Vec<u8>, mut value: u128) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(b);
+            return;
+        }
+
+        out.push(b | 0x80);
+    }
+}
+
+/// Read a LEB128-encoded varint.
+fn read_varint(input: &mut &[u8]) -> io::Result<u128> {
+    let mut value = 0u128;
+    let mut shift = 0;
+
+    loop {
+        let (&b, rest) = input
+            .split_first()
+            .ok_or_else(|| invalid("unexpected end of snapshot"))?;
+        *input = rest;
+
+        if shift >= 128 || (shift > 121 && (b & 0x7f) >> (128 - shift) != 0) {
+            return Err(invalid("varint overflows"));
+        }
+
+        value |= u128::from(b & 0x7f) << shift;
+
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+
+        shift += 7;
+    }
+}
+
+/// Zigzag-encode a signed delta, so that small negative values stay small.
+fn zigzag(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+/// Decode a zigzag-encoded value.
+fn unzigzag(value: u128) -> i128 {
+    ((value >> 1) as i128) ^ -((value & 1) as i128)
+}
+
+/// Read a varint which has to fit in a `usize`.
+fn read_usize(input: &mut &[u8], message: &str) -> io::Result<usize> {
+    usize::try_from(read_varint(input)?).map_err(|_| invalid(message))
+}
+
+/// Write a reuse policy.
+fn write_policy(out: &mut Vec<u8>, policy: ReusePolicy) {
+    match policy {
+        ReusePolicy::Lifo => out.push(0),
+        ReusePolicy::Fifo => out.push(1),
+        ReusePolicy::LowestFirst => out.push(2),
+        ReusePolicy::Quarantine(delay) => {
+            out.push(3);
+            write_varint(out, u128::from(delay));
+        }
+    }
+}
+
+/// Read a reuse policy.
+fn read_policy(input: &mut &[u8]) -> io::Result<ReusePolicy> {
+    let (&b, rest) = input
+        .split_first()
+        .ok_or_else(|| invalid("unexpected end of snapshot"))?;
+    *input = rest;
+
+    Ok(match b {
+        0 => ReusePolicy::Lifo,
+        1 => ReusePolicy::Fifo,
+        2 => ReusePolicy::LowestFirst,
+        3 => {
+            let delay = u64::try_from(read_varint(input)?)
+                .map_err(|_| invalid("snapshot quarantine delay overflows"))?;
+            ReusePolicy::Quarantine(delay)
+        }
+        _ => return Err(invalid("snapshot has an unknown reuse policy")),
+    })
+}
+
+impl<I> Slab<I>
+where
+    I: Id,
+{
+    /// Write a compact binary snapshot of the allocator, which can be read
+    /// back with [Slab::read_snapshot].
+    ///
+    /// The format is stable across versions of this crate, and consists of:
+    /// * The magic bytes `IDAL`.
+    /// * A format version byte, currently `2`.
+    /// * An allocator kind byte, where `1` is [Slab].
+    /// * The width of the id type in bytes.
+    /// * The [reuse policy][crate::ReusePolicy], as a byte where `0` is
+    ///   `Lifo`, `1` is `Fifo`, `2` is `LowestFirst` and `3` is `Quarantine`,
+    ///   which is followed by its delay as a LEB128 varint.
+    /// * The number of ids above the maximum id, not counting the none
+    ///   sentinel, as a LEB128 varint.
+    /// * The number of reserved ranges of ids, as a LEB128 varint, followed by
+    ///   each range in increasing order as two LEB128 varints: the distance
+    ///   from the end of the previous range, and its length.
+    /// * The number of slots, as a LEB128 varint.
+    /// * A bitmap with one bit per slot, least significant bit first, where
+    ///   the bits of free slots are set.
+    /// * The free ids in the order they will be reused, each encoded as a
+    ///   zigzag LEB128 varint of the difference from the previous free id.
+    /// * A little-endian CRC-32 of all preceding bytes.
+    ///
+    /// Since every slot takes up a bit, the memory needed to read a snapshot
+    /// is bounded by its size.
+    ///
+    /// Fails with an error of kind [io::ErrorKind::InvalidData] if the free
+    /// list of the allocator has been corrupted into a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    ///
+    /// let mut alloc = Slab::<u32>::new();
+    ///
+    /// for _ in 0..1000 {
+    ///     alloc.next();
+    /// }
+    ///
+    /// alloc.free(10);
+    /// alloc.free(500);
+    ///
+    /// let mut buf = Vec::new();
+    /// alloc.write_snapshot(&mut buf)?;
+    /// assert!(buf.len() < 150);
+    ///
+    /// let mut alloc = Slab::<u32>::read_snapshot(&buf[..])?;
+    /// assert_eq!(500, alloc.next());
+    /// assert_eq!(10, alloc.next());
+    /// assert_eq!(1000, alloc.next());
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    ///
+    /// Reserved ids, the maximum id and the reuse policy are restored:
+    ///
+    /// ```rust
+    /// use idalloc::{AllocError, ReusePolicy, Slab};
+    ///
+    /// let mut alloc = Slab::<u16>::builder()
+    ///     .reserve(0..10)
+    ///     .reserve(12..20)
+    ///     .max(20)
+    ///     .reuse(ReusePolicy::LowestFirst)
+    ///     .build();
+    ///
+    /// assert_eq!(10, alloc.next());
+    /// assert_eq!(11, alloc.next());
+    /// assert_eq!(20, alloc.next());
+    /// alloc.free(20);
+    /// alloc.free(10);
+    ///
+    /// let mut buf = Vec::new();
+    /// alloc.write_snapshot(&mut buf)?;
+    ///
+    /// let mut alloc = Slab::<u16>::read_snapshot(&buf[..])?;
+    /// assert_eq!(ReusePolicy::LowestFirst, alloc.reuse_policy());
+    /// assert_eq!(3, alloc.capacity());
+    /// assert_eq!(Ok(10), alloc.try_next());
+    /// assert_eq!(Ok(20), alloc.try_next());
+    /// assert_eq!(Err(AllocError::Exhausted), alloc.try_next());
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn write_snapshot<W>(&self, out: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        let mut free = Vec::new();
+        let mut bitmap = vec![0u8; self.data.len().div_ceil(8)];
+        let mut current = self.next;
+
+        while let Some(&link) = self.data.get(self.slot(current)) {
+            if free.len() == self.data.len() {
+                return Err(invalid("free list has a cycle"));
+            }
+
+            let slot = self.slot(current);
+            bitmap[slot / 8] |= 1 << (slot % 8);
+            free.push(current);
+            current = link;
+        }
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&MAGIC);
+        buf.push(VERSION);
+        buf.push(KIND_SLAB);
+        buf.push(width::<I>());
+        write_policy(&mut buf, self.reuse_policy());
+        write_varint(&mut buf, (I::none().as_usize() - self.limit) as u128);
+        write_varint(&mut buf, self.reserved.iter().len() as u128);
+
+        let mut end = 0;
+
+        for range in self.reserved.iter() {
+            write_varint(&mut buf, (range.start - end) as u128);
+            write_varint(&mut buf, (range.end - range.start) as u128);
+            end = range.end;
+        }
+
+        write_varint(&mut buf, self.data.len() as u128);
+        buf.extend_from_slice(&bitmap);
+
+        let mut previous = 0i128;
+
+        for index in free {
+            let index = index.as_u128() as i128;
+            write_varint(&mut buf, zigzag(index.wrapping_sub(previous)));
+            previous = index;
+        }
+
+        let crc = crc32(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        out.write_all(&buf)
+    }
+
+    /// Read a binary snapshot written by [Slab::write_snapshot].
+    ///
+    /// Snapshots with a bad checksum, an unsupported version, a different id
+    /// width, or an inconsistent set of free ids are rejected with an error of
+    /// kind [io::ErrorKind::InvalidData].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use idalloc::Slab;
+    /// use std::io;
+    ///
+    /// let mut alloc = Slab::<u16>::new();
+    /// alloc.next();
+    ///
+    /// let mut buf = Vec::new();
+    /// alloc.write_snapshot(&mut buf)?;
+    ///
+    /// // Snapshots can't be read using a different id type.
+    /// let e = Slab::<u32>::read_snapshot(&buf[..]).err().unwrap();
+    /// assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    ///
+    /// // Corruption is detected through the checksum.
+    /// buf[8] ^= 1;
+    /// let e = Slab::<u16>::read_snapshot(&buf[..]).err().unwrap();
+    /// assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    ///
+    /// // A tiny snapshot claiming 2^62 slots is rejected without allocating
+    /// // memory for them, since it doesn't have room for their bitmap.
+    /// let mut buf = b"IDAL\x02\x01\x08\x00\x00\x00".to_vec();
+    /// buf.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40]);
+    /// buf.extend_from_slice(&crc32(&buf).to_le_bytes());
+    /// let e = Slab::<u64>::read_snapshot(&buf[..]).err().unwrap();
+    /// assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    /// assert_eq!("snapshot is truncated", e.to_string());
+    /// # fn crc32(bytes: &[u8]) -> u32 {
+    /// #     let mut crc = !0u32;
+    /// #     for &b in bytes {
+    /// #         crc ^= u32::from(b);
+    /// #         for _ in 0..8 {
+    /// #             crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+    /// #         }
+    /// #     }
+    /// #     !crc
+    /// # }
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn read_snapshot<R>(mut input: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut buf = Vec::new();
+        input.read_to_end(&mut buf)?;
+
+        if buf.len() < MAGIC.len() + 7 {
+            return Err(invalid("snapshot is truncated"));
+        }
+
+        let (body, crc) = buf.split_at(buf.len() - 4);
+        let mut expected = [0u8; 4];
+        expected.copy_from_slice(crc);
+
+        if crc32(body) != u32::from_le_bytes(expected) {
+            return Err(invalid("snapshot checksum mismatch"));
+        }
+
+        let (magic, header) = body.split_at(MAGIC.len());
+
+        if magic != MAGIC {
+            return Err(invalid("snapshot has bad magic bytes"));
+        }
+
+        if header[0] != VERSION {
+            return Err(invalid("unsupported snapshot version"));
+        }
+
+        if header[1] != KIND_SLAB {
+            return Err(invalid("snapshot is not of a slab"));
+        }
+
+        if header[2] != width::<I>() {
+            return Err(invalid("snapshot id width mismatch"));
+        }
+
+        let mut input = &header[3..];
+        let policy = read_policy(&mut input)?;
+        let limit = I::none()
+            .as_usize()
+            .checked_sub(read_usize(&mut input, "snapshot maximum id overflows")?)
+            .ok_or_else(|| invalid("snapshot maximum id overflows"))?;
+        let count = read_usize(&mut input, "snapshot has too many reserved ranges")?;
+
+        // NB: every reserved range takes up at least two bytes.
+        if count > input.len() / 2 {
+            return Err(invalid("snapshot is truncated"));
+        }
+
+        let mut reserved = Vec::with_capacity(count);
+        let mut end = 0usize;
+
+        for _ in 0..count {
+            let start = read_usize(&mut input, "snapshot reserved range overflows")?
+                .checked_add(end)
+                .ok_or_else(|| invalid("snapshot reserved range overflows"))?;
+            end = read_usize(&mut input, "snapshot reserved range overflows")?
+                .checked_add(start)
+                .ok_or_else(|| invalid("snapshot reserved range overflows"))?;
+            reserved.push(start..end);
+        }
+
+        let slab = Self::from_config(reserved, limit, policy)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let len = read_varint(&mut input)?;
+
+        if len > slab.capacity() as u128 {
+            return Err(invalid("snapshot has too many slots"));
+        }
+
+        let len = len as usize;
+
+        // NB: checking the size of the bitmap before allocating any slots
+        // bounds the memory used by the size of the snapshot.
+        if len.div_ceil(8) > input.len() {
+            return Err(invalid("snapshot is truncated"));
+        }
+
+        let (bitmap, rest) = input.split_at(len.div_ceil(8));
+        input = rest;
+
+        let padding = (bitmap.len() * 8 - len) as u32;
+
+        if bitmap.last().is_some_and(|b| b.leading_zeros() < padding) {
+            return Err(invalid("snapshot has free slots out of bounds"));
+        }
+
+        let count = bitmap
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum::<usize>();
+
+        // NB: the tail of the free list links to the first unused id, which
+        // is the none sentinel if every id has been handed out.
+        let end = slab.reserved.id_at(len);
+        let tail = I::from_usize(end).unwrap_or_else(I::none);
+        let mut data = Vec::new();
+        data.try_reserve_exact(len)
+            .map_err(|_| invalid("snapshot has too many slots to allocate"))?;
+        data.resize(len, I::none());
+
+        let mut next = tail;
+        let mut last = None::<usize>;
+        let mut previous = 0i128;
+
+        for _ in 0..count {
+            let delta = unzigzag(read_varint(&mut input)?);
+            let index = previous.wrapping_add(delta);
+            previous = index;
+
+            if index < 0 || index as u128 >= end as u128 || slab.reserved.contains(index as usize) {
+                return Err(invalid("snapshot free id out of bounds"));
+            }
+
+            let id = I::from_usize(index as usize)
+                .ok_or_else(|| invalid("snapshot free id out of bounds"))?;
+            let slot = slab.slot(id);
+
+            if bitmap[slot / 8] & (1 << (slot % 8)) == 0 {
+                return Err(invalid("snapshot free id is not marked as free"));
+            }
+
+            if !data[slot].is_none() || last == Some(slot) {
+                return Err(invalid("snapshot has duplicate free ids"));
+            }
+
+            match last {
+                Some(last) => data[last] = id,
+                None => next = id,
+            }
+
+            data[slot] = tail;
+            last = Some(slot);
+        }
+
+        if !input.is_empty() {
+            return Err(invalid("snapshot has trailing bytes"));
+        }
+
+        slab.with_parts(data, next)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
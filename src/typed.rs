@@ -0,0 +1,203 @@
+use crate::Id;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+/// An id tagged with the marker type `T`, so that ids allocated for different
+/// purposes can't be mixed up.
+///
+/// This has the same size and representation as `I`, and implements [Id] by
+/// delegating to it.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "alloc", doc = "```rust")]
+#[cfg_attr(not(feature = "alloc"), doc = "```rust,ignore")]
+/// use idalloc::{TypedId, TypedSlab};
+///
+/// struct Texture;
+/// struct Mesh;
+///
+/// let mut textures = TypedSlab::<Texture>::new();
+/// let mut meshes = TypedSlab::<Mesh>::new();
+///
+/// let texture = textures.next();
+/// let mesh = meshes.next();
+/// assert_eq!(0, texture.get());
+/// assert_eq!(0, mesh.get());
+///
+/// assert!(textures.free(texture));
+/// assert!(meshes.free(TypedId::new(0)));
+/// ```
+///
+/// Freeing an id in the wrong allocator doesn't compile:
+///
+/// ```compile_fail
+/// use idalloc::TypedSlab;
+///
+/// struct Texture;
+/// struct Mesh;
+///
+/// let mut textures = TypedSlab::<Texture>::new();
+/// let mut meshes = TypedSlab::<Mesh>::new();
+///
+/// let texture = textures.next();
+/// meshes.free(texture);
+/// ```
+#[repr(transparent)]
+pub struct TypedId<T, I = u32> {
+    id: I,
+    // NB: `fn() -> T` keeps the id `Send` and `Sync` regardless of `T`.
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, I> TypedId<T, I> {
+    /// Construct a typed id out of a raw id.
+    pub const fn new(id: I) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the raw id.
+    pub fn get(self) -> I
+    where
+        I: Copy,
+    {
+        self.id
+    }
+}
+
+impl<T, I> Clone for TypedId<T, I>
+where
+    I: Copy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, I> Copy for TypedId<T, I> where I: Copy {}
+
+impl<T, I> PartialEq for TypedId<T, I>
+where
+    I: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T, I> Eq for TypedId<T, I> where I: Eq {}
+
+impl<T, I> PartialOrd for TypedId<T, I>
+where
+    I: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.id.partial_cmp(&other.id)
+    }
+}
+
+impl<T, I> Ord for TypedId<T, I>
+where
+    I: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<T, I> Hash for TypedId<T, I>
+where
+    I: Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.id.hash(state);
+    }
+}
+
+impl<T, I> fmt::Debug for TypedId<T, I>
+where
+    I: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("TypedId").field(&self.id).finish()
+    }
+}
+
+impl<T, I> fmt::Display for TypedId<T, I>
+where
+    I: fmt::Display,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(fmt)
+    }
+}
+
+impl<T, I> Id for TypedId<T, I>
+where
+    I: Id,
+{
+    const INITIAL: Self = Self::new(I::INITIAL);
+    const NONE: Self = Self::new(I::NONE);
+
+    #[inline(always)]
+    fn as_usize(self) -> usize {
+        self.id.as_usize()
+    }
+
+    #[inline(always)]
+    fn from_usize(index: usize) -> Option<Self> {
+        Some(Self::new(I::from_usize(index)?))
+    }
+
+    #[inline(always)]
+    fn as_u128(self) -> u128 {
+        self.id.as_u128()
+    }
+
+    #[inline(always)]
+    fn from_u128(index: u128) -> Option<Self> {
+        Some(Self::new(I::from_u128(index)?))
+    }
+
+    #[inline(always)]
+    fn increment(self) -> Self {
+        Self::new(self.id.increment())
+    }
+
+    #[inline(always)]
+    fn checked_increment(self) -> Option<Self> {
+        Some(Self::new(self.id.checked_increment()?))
+    }
+
+    #[inline(always)]
+    fn take(&mut self) -> Self {
+        Self::new(self.id.take())
+    }
+
+    #[inline(always)]
+    fn expect(self, m: &str) -> Self {
+        Self::new(self.id.expect(m))
+    }
+
+    #[inline(always)]
+    fn into_option(self) -> Option<Self> {
+        Some(Self::new(self.id.into_option()?))
+    }
+
+    #[inline(always)]
+    fn is_none(self) -> bool {
+        self.id.is_none()
+    }
+}
+
+/// A [Slab][crate::Slab] which allocates [TypedId]s tagged with `T`.
+#[cfg(feature = "alloc")]
+pub type TypedSlab<T, I = u32> = crate::Slab<TypedId<T, I>>;
@@ -0,0 +1,103 @@
+//! Model checks for [idalloc::AtomicSlab].
+//!
+//! Run with:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --test loom --release
+//! ```
+
+#![cfg(loom)]
+
+use idalloc::AtomicSlab;
+use loom::sync::Arc;
+use loom::thread;
+
+#[test]
+fn concurrent_next_is_unique() {
+    loom::model(|| {
+        let alloc = Arc::new(AtomicSlab::<u8>::with_capacity(2));
+
+        let a = {
+            let alloc = alloc.clone();
+            thread::spawn(move || alloc.next())
+        };
+
+        let b = alloc.next();
+        let a = a.join().unwrap();
+        assert_ne!(a, b);
+    });
+}
+
+#[test]
+fn concurrent_free_and_next() {
+    loom::model(|| {
+        let alloc = Arc::new(AtomicSlab::<u8>::with_capacity(2));
+        let first = alloc.next();
+        let second = alloc.next();
+
+        let t = {
+            let alloc = alloc.clone();
+            thread::spawn(move || {
+                assert!(alloc.free(first));
+            })
+        };
+
+        assert!(alloc.free(second));
+        t.join().unwrap();
+
+        let a = alloc.next();
+        let b = alloc.next();
+        assert_ne!(a, b);
+        assert!(alloc.try_next().is_err());
+    });
+}
+
+#[test]
+fn aba_is_detected() {
+    loom::model(|| {
+        let alloc = Arc::new(AtomicSlab::<u8>::with_capacity(3));
+        let ids = [alloc.next(), alloc.next(), alloc.next()];
+
+        for &id in &ids {
+            alloc.free(id);
+        }
+
+        // One thread pops two ids and pushes the first one back, while the
+        // other pops concurrently.
+        let t = {
+            let alloc = alloc.clone();
+            thread::spawn(move || {
+                let a = alloc.next();
+                let b = alloc.next();
+                alloc.free(a);
+                b
+            })
+        };
+
+        let c = alloc.next();
+        let b = t.join().unwrap();
+        assert_ne!(b, c);
+
+        let d = alloc.next();
+        assert_ne!(b, d);
+        assert_ne!(c, d);
+        assert!(alloc.try_next().is_err());
+    });
+}
+
+#[test]
+fn double_free_is_rejected() {
+    loom::model(|| {
+        let alloc = Arc::new(AtomicSlab::<u8>::with_capacity(1));
+        let id = alloc.next();
+
+        let t = {
+            let alloc = alloc.clone();
+            thread::spawn(move || alloc.free(id))
+        };
+
+        let freed = alloc.free(id);
+        assert!(freed ^ t.join().unwrap());
+        assert_eq!(id, alloc.next());
+    });
+}